@@ -63,6 +63,14 @@ function load_highscores(text) {
             onHighscoreLoaded(highscores);
         }
     }
+    //  the top run's replay, base64 like save_replay's, to race its ghost
+    if (dec && dec.top_replay) {
+        try {
+            window.localStorage.replay_top = dec.top_replay;
+        } catch (error) {
+            clog(`load_highscores error: ${error}`);
+        }
+    }
 }
 
 function blind_backend_post(arg) {
@@ -108,7 +116,7 @@ function register_highscore(arg) {
     arg = consume_js_object(arg);
     let atend = true;
     const score = 0 + arg.score;
-    const username = observeTeleData.username;
+    const username = arg.name ? arg.name : observeTeleData.username;
     for (let i = 0; i != 10; i++) {
         if (highscores[i].score < arg.score) {
             highscores.splice(i, 0, { name: username, score: score });
@@ -136,12 +144,66 @@ function register_highscore(arg) {
 function read_highscores() {
     let ret = {};
     //  because of interop shenanigans, I need a legit object with keys, not an array
-    for (let i = 0, n = highscores.length(); i != n; i++) {
+    for (let i = 0, n = highscores.length; i != n; i++) {
         ret[`${i}`] = highscores[i];
     }
     return ret;
 }
 
+//  Replays are small byte buffers; keep them in localStorage as base64
+function save_replay(kindarg, dataarg) {
+    const kind = consume_js_object(kindarg);
+    const data = consume_js_object(dataarg);
+    let bin = '';
+    for (let i = 0; i != data.length; i++) {
+        bin += String.fromCharCode(data[i]);
+    }
+    try {
+        window.localStorage[`replay_${kind}`] = btoa(bin);
+    } catch (error) {
+        clog(`save_replay error: ${error}`);
+    }
+}
+
+function load_replay(kindarg) {
+    const kind = consume_js_object(kindarg);
+    const stored = window.localStorage[`replay_${kind}`];
+    if (!stored) {
+        return -1;
+    }
+    const bin = atob(stored);
+    let data = new Uint8Array(bin.length);
+    for (let i = 0; i != bin.length; i++) {
+        data[i] = bin.charCodeAt(i);
+    }
+    return js_object(data);
+}
+
+// Gamepad ix as input bits: 1 left, 2 right, 4 fire, 8 pause (see input.rs)
+function read_gamepad(ix) {
+    const pads = navigator.getGamepads ? navigator.getGamepads() : [];
+    const pad = pads[ix];
+    if (!pad || !pad.connected) {
+        return 0;
+    }
+    const pressed = (b) => b < pad.buttons.length && pad.buttons[b].pressed;
+    const axis = pad.axes.length > 0 ? pad.axes[0] : 0;
+    let bits = 0;
+    if (axis < -0.5 || pressed(14)) {
+        bits |= 1;
+    }
+    if (axis > 0.5 || pressed(15)) {
+        bits |= 2;
+    }
+    if (pressed(0) || pressed(1)) {
+        bits |= 4;
+    }
+    if (pressed(9)) {
+        bits |= 8;
+    }
+    return bits;
+}
+
 // Will be called when wasm_exports and wasm_memory will be available
 function on_init() {
     /// Call rust app function with string argument
@@ -153,6 +215,9 @@ function register_plugin(importObject) {
     importObject.env.queue_telemetry = queue_telemetry;
     importObject.env.register_highscore = register_highscore;
     importObject.env.read_highscores = read_highscores;
+    importObject.env.save_replay = save_replay;
+    importObject.env.load_replay = load_replay;
+    importObject.env.read_gamepad = read_gamepad;
 }
 
 // register this plugin in miniquad, required to make plugin's functions available from rust
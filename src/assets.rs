@@ -1,6 +1,7 @@
 //  assets.rs
 
 use crate::sprite;
+use futures::future::join_all;
 use futures::join;
 use macroquad::audio;
 use macroquad::prelude as mq;
@@ -8,10 +9,9 @@ use std::borrow::Borrow;
 use std::borrow::Cow;
 use std::fmt;
 
-pub const ASSUMED_SCREEN_WIDTH: i32 = 2400;
-
 pub struct Assets {
     pub sprites: Vec<sprite::LoadedSprite>,
+    pub hitboxes: Vec<sprite::Hitbox>,
     pub sounds: Vec<audio::Sound>,
     pub basslines: Vec<BasslineSound>,
 }
@@ -35,14 +35,116 @@ pub const IX_EXPLOSION_6: usize = 11;
 pub const IX_EXPLOSION_7: usize = 12;
 pub const IX_EXPLOSION_8: usize = 13;
 pub const IX_BOMB: usize = 14;
+pub const IX_UFO_BLUE: usize = 15;
+pub const IX_UFO_GREEN: usize = 16;
+pub const IX_UFO_RED: usize = 17;
+pub const IX_UFO_YELLOW: usize = 18;
+pub const IX_BOMB_ZIGZAG: usize = 19;
+pub const IX_BOMB_PLUNGER: usize = 20;
+pub const IX_POWERUP_BOLT: usize = 21;
+pub const IX_POWERUP_STAR: usize = 22;
+pub const IX_POWERUP_SHIELD: usize = 23;
+pub const IX_POWERUP_PILL: usize = 24;
+pub const IX_SHIELD_1: usize = 25;
+pub const IX_SHIELD_2: usize = 26;
+pub const IX_SHIELD_3: usize = 27;
+pub const IX_BOSS: usize = 28;
+//  20 meteors: brown big1-4, med1, med3, small1-2, tiny1-2, then grey
+//  big1-4, med1-2, small1-2, tiny1-2
+pub const IX_METEOR_0: usize = 29;
+//  the eleven player ships besides the original, which stays at IX_PLAYER
+pub const IX_PLAYER_SHIPS: usize = 49;
+//  damage overlays, three per hull; then the 20 frames of engine fire
+pub const IX_DAMAGE_0: usize = 60;
+pub const IX_FIRE_0: usize = 69;
 
-pub static PLAYER_SPRITE: sprite::Sprite = sprite::Sprite {
-    index: IX_PLAYER,
-    filename: b_str("data/playerShip3_blue.png"),
-    width_pix: 95,
-    height_pix: 75,
-    scale: 1.0,
-};
+//  Three hulls in four colours, see ship.rs; hull 3 in blue is the original
+pub static PLAYER_SHIPS: [sprite::Sprite; 12] = [
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 0,
+        filename: b_str("data/playerShip1_blue.png"),
+        width_pix: 99,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 1,
+        filename: b_str("data/playerShip1_green.png"),
+        width_pix: 99,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 2,
+        filename: b_str("data/playerShip1_orange.png"),
+        width_pix: 99,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 3,
+        filename: b_str("data/playerShip1_red.png"),
+        width_pix: 99,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 4,
+        filename: b_str("data/playerShip2_blue.png"),
+        width_pix: 112,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 5,
+        filename: b_str("data/playerShip2_green.png"),
+        width_pix: 112,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 6,
+        filename: b_str("data/playerShip2_orange.png"),
+        width_pix: 112,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 7,
+        filename: b_str("data/playerShip2_red.png"),
+        width_pix: 112,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER,
+        filename: b_str("data/playerShip3_blue.png"),
+        width_pix: 95,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 8,
+        filename: b_str("data/playerShip3_green.png"),
+        width_pix: 98,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 9,
+        filename: b_str("data/playerShip3_orange.png"),
+        width_pix: 98,
+        height_pix: 75,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_PLAYER_SHIPS + 10,
+        filename: b_str("data/playerShip3_red.png"),
+        width_pix: 98,
+        height_pix: 75,
+        scale: 1.0,
+    },
+];
 
 pub static LASER_SPRITE: sprite::Sprite = sprite::Sprite {
     index: IX_LASER,
@@ -76,6 +178,14 @@ pub static ENEMY3_SPRITE: sprite::Sprite = sprite::Sprite {
     scale: 1.3,
 };
 
+pub static BOSS_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_BOSS,
+    filename: b_str("data/Enemies/enemyBlack5.png"),
+    width_pix: 97,
+    height_pix: 84,
+    scale: 3.5,
+};
+
 pub static BOMB_SPRITE: sprite::Sprite = sprite::Sprite {
     index: IX_BOMB,
     filename: b_str("data/Lasers/laserRed07.png"),
@@ -84,6 +194,464 @@ pub static BOMB_SPRITE: sprite::Sprite = sprite::Sprite {
     scale: 1.0,
 };
 
+pub static BOMB_ZIGZAG_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_BOMB_ZIGZAG,
+    filename: b_str("data/Lasers/laserGreen13.png"),
+    width_pix: 9,
+    height_pix: 37,
+    scale: 0.8,
+};
+
+pub static BOMB_PLUNGER_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_BOMB_PLUNGER,
+    filename: b_str("data/Lasers/laserRed10.png"),
+    width_pix: 37,
+    height_pix: 36,
+    scale: 0.7,
+};
+
+pub static POWERUP_BOLT_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_POWERUP_BOLT,
+    filename: b_str("data/Power-ups/powerupBlue_bolt.png"),
+    width_pix: 34,
+    height_pix: 33,
+    scale: 1.2,
+};
+
+pub static POWERUP_STAR_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_POWERUP_STAR,
+    filename: b_str("data/Power-ups/powerupYellow_star.png"),
+    width_pix: 34,
+    height_pix: 33,
+    scale: 1.2,
+};
+
+pub static POWERUP_SHIELD_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_POWERUP_SHIELD,
+    filename: b_str("data/Power-ups/powerupGreen_shield.png"),
+    width_pix: 34,
+    height_pix: 33,
+    scale: 1.2,
+};
+
+pub static POWERUP_PILL_SPRITE: sprite::Sprite = sprite::Sprite {
+    index: IX_POWERUP_PILL,
+    filename: b_str("data/Power-ups/pill_red.png"),
+    width_pix: 22,
+    height_pix: 21,
+    scale: 1.5,
+};
+
+//  The bubble around a shielded player; the bigger ones are the fresher shield
+pub static SHIELD_SPRITES: [sprite::Sprite; 3] = [
+    sprite::Sprite {
+        index: IX_SHIELD_1,
+        filename: b_str("data/Effects/shield1.png"),
+        width_pix: 133,
+        height_pix: 108,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_SHIELD_2,
+        filename: b_str("data/Effects/shield2.png"),
+        width_pix: 143,
+        height_pix: 119,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_SHIELD_3,
+        filename: b_str("data/Effects/shield3.png"),
+        width_pix: 144,
+        height_pix: 137,
+        scale: 1.0,
+    },
+];
+
+//  Laid out as described at IX_METEOR_0
+pub static METEOR_SPRITES: [sprite::Sprite; 20] = [
+    sprite::Sprite {
+        index: IX_METEOR_0 + 0,
+        filename: b_str("data/Meteors/meteorBrown_big1.png"),
+        width_pix: 101,
+        height_pix: 84,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 1,
+        filename: b_str("data/Meteors/meteorBrown_big2.png"),
+        width_pix: 120,
+        height_pix: 98,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 2,
+        filename: b_str("data/Meteors/meteorBrown_big3.png"),
+        width_pix: 89,
+        height_pix: 82,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 3,
+        filename: b_str("data/Meteors/meteorBrown_big4.png"),
+        width_pix: 98,
+        height_pix: 96,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 4,
+        filename: b_str("data/Meteors/meteorBrown_med1.png"),
+        width_pix: 43,
+        height_pix: 43,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 5,
+        filename: b_str("data/Meteors/meteorBrown_med3.png"),
+        width_pix: 45,
+        height_pix: 40,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 6,
+        filename: b_str("data/Meteors/meteorBrown_small1.png"),
+        width_pix: 28,
+        height_pix: 28,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 7,
+        filename: b_str("data/Meteors/meteorBrown_small2.png"),
+        width_pix: 29,
+        height_pix: 26,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 8,
+        filename: b_str("data/Meteors/meteorBrown_tiny1.png"),
+        width_pix: 18,
+        height_pix: 18,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 9,
+        filename: b_str("data/Meteors/meteorBrown_tiny2.png"),
+        width_pix: 16,
+        height_pix: 15,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 10,
+        filename: b_str("data/Meteors/meteorGrey_big1.png"),
+        width_pix: 101,
+        height_pix: 84,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 11,
+        filename: b_str("data/Meteors/meteorGrey_big2.png"),
+        width_pix: 120,
+        height_pix: 98,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 12,
+        filename: b_str("data/Meteors/meteorGrey_big3.png"),
+        width_pix: 89,
+        height_pix: 82,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 13,
+        filename: b_str("data/Meteors/meteorGrey_big4.png"),
+        width_pix: 98,
+        height_pix: 96,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 14,
+        filename: b_str("data/Meteors/meteorGrey_med1.png"),
+        width_pix: 43,
+        height_pix: 43,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 15,
+        filename: b_str("data/Meteors/meteorGrey_med2.png"),
+        width_pix: 45,
+        height_pix: 40,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 16,
+        filename: b_str("data/Meteors/meteorGrey_small1.png"),
+        width_pix: 28,
+        height_pix: 28,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 17,
+        filename: b_str("data/Meteors/meteorGrey_small2.png"),
+        width_pix: 29,
+        height_pix: 26,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 18,
+        filename: b_str("data/Meteors/meteorGrey_tiny1.png"),
+        width_pix: 18,
+        height_pix: 18,
+        scale: 1.2,
+    },
+    sprite::Sprite {
+        index: IX_METEOR_0 + 19,
+        filename: b_str("data/Meteors/meteorGrey_tiny2.png"),
+        width_pix: 16,
+        height_pix: 15,
+        scale: 1.2,
+    },
+];
+
+//  Drawn over the player's hull as it takes a beating, see ship.rs
+pub static DAMAGE_SPRITES: [sprite::Sprite; 9] = [
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 0,
+        filename: b_str("data/Damage/playerShip1_damage1.png"),
+        width_pix: 99,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 1,
+        filename: b_str("data/Damage/playerShip1_damage2.png"),
+        width_pix: 99,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 2,
+        filename: b_str("data/Damage/playerShip1_damage3.png"),
+        width_pix: 100,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 3,
+        filename: b_str("data/Damage/playerShip2_damage1.png"),
+        width_pix: 111,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 4,
+        filename: b_str("data/Damage/playerShip2_damage2.png"),
+        width_pix: 112,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 5,
+        filename: b_str("data/Damage/playerShip2_damage3.png"),
+        width_pix: 112,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 6,
+        filename: b_str("data/Damage/playerShip3_damage1.png"),
+        width_pix: 97,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 7,
+        filename: b_str("data/Damage/playerShip3_damage2.png"),
+        width_pix: 97,
+        height_pix: 76,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_DAMAGE_0 + 8,
+        filename: b_str("data/Damage/playerShip3_damage3.png"),
+        width_pix: 97,
+        height_pix: 76,
+        scale: 1.0,
+    },
+];
+
+pub static FIRE_SPRITES: [sprite::Sprite; 20] = [
+    sprite::Sprite {
+        index: IX_FIRE_0 + 0,
+        filename: b_str("data/Effects/fire00.png"),
+        width_pix: 16,
+        height_pix: 40,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 1,
+        filename: b_str("data/Effects/fire01.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 2,
+        filename: b_str("data/Effects/fire02.png"),
+        width_pix: 14,
+        height_pix: 32,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 3,
+        filename: b_str("data/Effects/fire03.png"),
+        width_pix: 14,
+        height_pix: 34,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 4,
+        filename: b_str("data/Effects/fire04.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 5,
+        filename: b_str("data/Effects/fire05.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 6,
+        filename: b_str("data/Effects/fire06.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 7,
+        filename: b_str("data/Effects/fire07.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 8,
+        filename: b_str("data/Effects/fire08.png"),
+        width_pix: 16,
+        height_pix: 40,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 9,
+        filename: b_str("data/Effects/fire09.png"),
+        width_pix: 16,
+        height_pix: 40,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 10,
+        filename: b_str("data/Effects/fire10.png"),
+        width_pix: 16,
+        height_pix: 40,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 11,
+        filename: b_str("data/Effects/fire11.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 12,
+        filename: b_str("data/Effects/fire12.png"),
+        width_pix: 14,
+        height_pix: 32,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 13,
+        filename: b_str("data/Effects/fire13.png"),
+        width_pix: 14,
+        height_pix: 34,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 14,
+        filename: b_str("data/Effects/fire14.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 15,
+        filename: b_str("data/Effects/fire15.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 16,
+        filename: b_str("data/Effects/fire16.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 17,
+        filename: b_str("data/Effects/fire17.png"),
+        width_pix: 14,
+        height_pix: 31,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 18,
+        filename: b_str("data/Effects/fire18.png"),
+        width_pix: 16,
+        height_pix: 41,
+        scale: 1.0,
+    },
+    sprite::Sprite {
+        index: IX_FIRE_0 + 19,
+        filename: b_str("data/Effects/fire19.png"),
+        width_pix: 16,
+        height_pix: 41,
+        scale: 1.0,
+    },
+];
+
+pub static UFO_SPRITES: [sprite::Sprite; 4] = [
+    sprite::Sprite {
+        index: IX_UFO_BLUE,
+        filename: b_str("data/ufoBlue.png"),
+        width_pix: 91,
+        height_pix: 91,
+        scale: 0.9,
+    },
+    sprite::Sprite {
+        index: IX_UFO_GREEN,
+        filename: b_str("data/ufoGreen.png"),
+        width_pix: 91,
+        height_pix: 91,
+        scale: 0.9,
+    },
+    sprite::Sprite {
+        index: IX_UFO_RED,
+        filename: b_str("data/ufoRed.png"),
+        width_pix: 91,
+        height_pix: 91,
+        scale: 0.9,
+    },
+    sprite::Sprite {
+        index: IX_UFO_YELLOW,
+        filename: b_str("data/ufoYellow.png"),
+        width_pix: 91,
+        height_pix: 91,
+        scale: 0.9,
+    },
+];
+
 pub static EXPLOSION_SPRITES: [sprite::Sprite; 9] = [
     sprite::Sprite {
         index: IX_EXPLOSION_0,
@@ -150,6 +718,104 @@ pub static EXPLOSION_SPRITES: [sprite::Sprite; 9] = [
     },
 ];
 
+//  Every sprite, in IX_ order, so sprite metrics can be had without textures
+pub static ALL_SPRITES: [&sprite::Sprite; 89] = [
+    &PLAYER_SHIPS[8],
+    &LASER_SPRITE,
+    &ENEMY1_SPRITE,
+    &ENEMY2_SPRITE,
+    &ENEMY3_SPRITE,
+    &EXPLOSION_SPRITES[0],
+    &EXPLOSION_SPRITES[1],
+    &EXPLOSION_SPRITES[2],
+    &EXPLOSION_SPRITES[3],
+    &EXPLOSION_SPRITES[4],
+    &EXPLOSION_SPRITES[5],
+    &EXPLOSION_SPRITES[6],
+    &EXPLOSION_SPRITES[7],
+    &EXPLOSION_SPRITES[8],
+    &BOMB_SPRITE,
+    &UFO_SPRITES[0],
+    &UFO_SPRITES[1],
+    &UFO_SPRITES[2],
+    &UFO_SPRITES[3],
+    &BOMB_ZIGZAG_SPRITE,
+    &BOMB_PLUNGER_SPRITE,
+    &POWERUP_BOLT_SPRITE,
+    &POWERUP_STAR_SPRITE,
+    &POWERUP_SHIELD_SPRITE,
+    &POWERUP_PILL_SPRITE,
+    &SHIELD_SPRITES[0],
+    &SHIELD_SPRITES[1],
+    &SHIELD_SPRITES[2],
+    &BOSS_SPRITE,
+    &METEOR_SPRITES[0],
+    &METEOR_SPRITES[1],
+    &METEOR_SPRITES[2],
+    &METEOR_SPRITES[3],
+    &METEOR_SPRITES[4],
+    &METEOR_SPRITES[5],
+    &METEOR_SPRITES[6],
+    &METEOR_SPRITES[7],
+    &METEOR_SPRITES[8],
+    &METEOR_SPRITES[9],
+    &METEOR_SPRITES[10],
+    &METEOR_SPRITES[11],
+    &METEOR_SPRITES[12],
+    &METEOR_SPRITES[13],
+    &METEOR_SPRITES[14],
+    &METEOR_SPRITES[15],
+    &METEOR_SPRITES[16],
+    &METEOR_SPRITES[17],
+    &METEOR_SPRITES[18],
+    &METEOR_SPRITES[19],
+    &PLAYER_SHIPS[0],
+    &PLAYER_SHIPS[1],
+    &PLAYER_SHIPS[2],
+    &PLAYER_SHIPS[3],
+    &PLAYER_SHIPS[4],
+    &PLAYER_SHIPS[5],
+    &PLAYER_SHIPS[6],
+    &PLAYER_SHIPS[7],
+    &PLAYER_SHIPS[9],
+    &PLAYER_SHIPS[10],
+    &PLAYER_SHIPS[11],
+    &DAMAGE_SPRITES[0],
+    &DAMAGE_SPRITES[1],
+    &DAMAGE_SPRITES[2],
+    &DAMAGE_SPRITES[3],
+    &DAMAGE_SPRITES[4],
+    &DAMAGE_SPRITES[5],
+    &DAMAGE_SPRITES[6],
+    &DAMAGE_SPRITES[7],
+    &DAMAGE_SPRITES[8],
+    &FIRE_SPRITES[0],
+    &FIRE_SPRITES[1],
+    &FIRE_SPRITES[2],
+    &FIRE_SPRITES[3],
+    &FIRE_SPRITES[4],
+    &FIRE_SPRITES[5],
+    &FIRE_SPRITES[6],
+    &FIRE_SPRITES[7],
+    &FIRE_SPRITES[8],
+    &FIRE_SPRITES[9],
+    &FIRE_SPRITES[10],
+    &FIRE_SPRITES[11],
+    &FIRE_SPRITES[12],
+    &FIRE_SPRITES[13],
+    &FIRE_SPRITES[14],
+    &FIRE_SPRITES[15],
+    &FIRE_SPRITES[16],
+    &FIRE_SPRITES[17],
+    &FIRE_SPRITES[18],
+    &FIRE_SPRITES[19],
+];
+
+//  The hitbox table the simulation runs against; works headless
+pub fn load_hitboxes() -> Vec<sprite::Hitbox> {
+    return ALL_SPRITES.iter().map(|spr| sprite::hitbox(spr)).collect();
+}
+
 pub async fn load_sprite(spr: &'static sprite::Sprite) -> sprite::LoadedSprite {
     let fnb = spr.filename.borrow();
     /*
@@ -163,11 +829,12 @@ pub async fn load_sprite(spr: &'static sprite::Sprite) -> sprite::LoadedSprite {
 
     mq::info!("loading {}", fnb);
     let texture = mq::load_texture(fnb).await.unwrap();
+    let hb = sprite::hitbox(spr);
     return sprite::LoadedSprite {
         sprite: spr,
         texture: texture,
-        f_w: (spr.width_pix as f32) / (ASSUMED_SCREEN_WIDTH as f32) * spr.scale,
-        f_h: (spr.height_pix as f32) / (ASSUMED_SCREEN_WIDTH as f32) * spr.scale,
+        f_w: hb.f_w,
+        f_h: hb.f_h,
     };
 }
 
@@ -194,68 +861,55 @@ pub const IX_SOUND_POP_01: usize = 2;
 pub const IX_SOUND_POP_02: usize = 3;
 pub const IX_SOUND_POP_03: usize = 4;
 pub const IX_SOUND_POP_04: usize = 5;
+pub const IX_SOUND_UFO: usize = 6;
+
+//  There's no UFO sample in the asset pack, so make the classic warble:
+//  a tone swept up and down, as a short 16-bit mono WAV that loops cleanly.
+fn synth_ufo_wav() -> Vec<u8> {
+    const RATE: u32 = 22050;
+    const LFO_HZ: f32 = 6.0;
+    let samples = (RATE as f32 / LFO_HZ) as u32 * 2;
+    let mut pcm: Vec<u8> = Vec::new();
+    let mut phase: f32 = 0.0;
+    for n in 0..samples {
+        let t = n as f32 / RATE as f32;
+        let freq = 520.0 + 180.0 * (t * LFO_HZ * std::f32::consts::TAU).sin();
+        phase += freq / RATE as f32 * std::f32::consts::TAU;
+        let v = (phase.sin() * 0.3 * i16::MAX as f32) as i16;
+        pcm.extend_from_slice(&v.to_le_bytes());
+    }
+    let mut wav: Vec<u8> = Vec::new();
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + pcm.len() as u32).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes()); //  PCM
+    wav.extend_from_slice(&1u16.to_le_bytes()); //  mono
+    wav.extend_from_slice(&RATE.to_le_bytes());
+    wav.extend_from_slice(&(RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&(pcm.len() as u32).to_le_bytes());
+    wav.extend_from_slice(&pcm);
+    return wav;
+}
 
 pub async fn load_assets() -> Assets {
-    let mut vec: Vec<sprite::LoadedSprite> = Vec::new();
-    //  join!() macro only takes fixed-size tuple, not vec?
-    let (
-        s_player,
-        s_laser,
-        s_enemy1,
-        s_enemy2,
-        s_enemy3,
-        s_exp0,
-        s_exp1,
-        s_exp2,
-        s_exp3,
-        s_exp4,
-        s_exp5,
-        s_exp6,
-        s_exp7,
-        s_exp8,
-        s_bomb,
-    ) = join!(
-        load_sprite(&PLAYER_SPRITE),
-        load_sprite(&LASER_SPRITE),
-        load_sprite(&ENEMY1_SPRITE),
-        load_sprite(&ENEMY2_SPRITE),
-        load_sprite(&ENEMY3_SPRITE),
-        load_sprite(&EXPLOSION_SPRITES[0]),
-        load_sprite(&EXPLOSION_SPRITES[1]),
-        load_sprite(&EXPLOSION_SPRITES[2]),
-        load_sprite(&EXPLOSION_SPRITES[3]),
-        load_sprite(&EXPLOSION_SPRITES[4]),
-        load_sprite(&EXPLOSION_SPRITES[5]),
-        load_sprite(&EXPLOSION_SPRITES[6]),
-        load_sprite(&EXPLOSION_SPRITES[7]),
-        load_sprite(&EXPLOSION_SPRITES[8]),
-        load_sprite(&BOMB_SPRITE),
-    );
-    vec.push(s_player);
-    vec.push(s_laser);
-    vec.push(s_enemy1);
-    vec.push(s_enemy2);
-    vec.push(s_enemy3);
-    vec.push(s_exp0);
-    vec.push(s_exp1);
-    vec.push(s_exp2);
-    vec.push(s_exp3);
-    vec.push(s_exp4);
-    vec.push(s_exp5);
-    vec.push(s_exp6);
-    vec.push(s_exp7);
-    vec.push(s_exp8);
-    vec.push(s_bomb);
+    let vec: Vec<sprite::LoadedSprite> =
+        join_all(ALL_SPRITES.iter().map(|spr| load_sprite(spr))).await;
     mq::info!("loading sprites done");
 
     let mut snd: Vec<audio::Sound> = Vec::new();
-    let (sn_explosion, sn_laser, sn_pop01, sn_pop02, sn_pop03, sn_pop04) = join!(
+    let ufo_wav = synth_ufo_wav();
+    let (sn_explosion, sn_laser, sn_pop01, sn_pop02, sn_pop03, sn_pop04, sn_ufo) = join!(
         audio::load_sound("data/sound/explosion.mp3"),
         audio::load_sound("data/sound/laser.mp3"),
         audio::load_sound("data/sound/pop-01.mp3"),
         audio::load_sound("data/sound/pop-02.mp3"),
         audio::load_sound("data/sound/pop-03.mp3"),
         audio::load_sound("data/sound/pop-04.mp3"),
+        audio::load_sound_from_bytes(&ufo_wav),
     );
     snd.push(sn_explosion.unwrap());
     snd.push(sn_laser.unwrap());
@@ -263,6 +917,7 @@ pub async fn load_assets() -> Assets {
     snd.push(sn_pop02.unwrap());
     snd.push(sn_pop03.unwrap());
     snd.push(sn_pop04.unwrap());
+    snd.push(sn_ufo.unwrap());
 
     let mut bl: Vec<BasslineSound> = Vec::new();
     let (
@@ -339,6 +994,7 @@ pub async fn load_assets() -> Assets {
 
     return Assets {
         sprites: vec,
+        hitboxes: load_hitboxes(),
         sounds: snd,
         basslines: bl,
     };
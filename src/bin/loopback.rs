@@ -0,0 +1,172 @@
+//  loopback.rs
+//
+//  Plays an online co-op game between two made-up players over a Loopback
+//  pair, so netplay.rs can be tried against lag and lost packets without a
+//  network, and reports how much rolling back it took.
+//
+//  usage: loopback [--waves DIR] [latency-ms] [loss-percent] [seconds]
+//
+//  Defaults are 80 ms each way, 10% loss and 60 seconds of play. Both sides
+//  run frames of slightly different lengths, as two real machines would.
+//  At the end both games, and a straight run of the same inputs with no
+//  network at all, must agree. Exit status is 0 when they do, 1 when they
+//  don't, and 2 for bad arguments.
+
+use space_observers::assets;
+use space_observers::input;
+use space_observers::netplay;
+use space_observers::params;
+use space_observers::rng;
+use space_observers::ship;
+use space_observers::sprite;
+use space_observers::state;
+use space_observers::wave;
+use std::process;
+use std::rc::Rc;
+
+const FRAME_TIME: f32 = 1.0 / 60.0;
+
+struct Side {
+    transport: netplay::Transport,
+    session: netplay::Session,
+    state: state::State,
+    accumulator: f32,
+    rng: rng::Rng, //  drives both the player and the frame times
+    held: input::Input,
+}
+
+//  Wanders about, firing most of the time
+fn play(side: &mut Side) -> input::Input {
+    if rng::gen_range_usize(&mut side.rng, 0, 20) == 0 {
+        let way = rng::gen_range_usize(&mut side.rng, 0, 3);
+        side.held.left = way == 0;
+        side.held.right = way == 1;
+    }
+    side.held.fire = rng::gen_range_usize(&mut side.rng, 0, 3) != 0;
+    return side.held;
+}
+
+//  One frame's worth of ticks, not going past tick number until
+fn frame(side: &mut Side, hitboxes: &[sprite::Hitbox], until: usize) {
+    let delta_time = FRAME_TIME * rng::gen_range_f32(&mut side.rng, 0.8, 1.2);
+    netplay::receive(
+        &mut side.session,
+        &mut side.transport,
+        &mut side.state,
+        delta_time,
+        hitboxes,
+    );
+    let input = play(side);
+    side.accumulator += delta_time;
+    while side.accumulator >= params::TICK_TIME && side.session.tick < until {
+        if !netplay::tick(&mut side.session, &mut side.state, input, hitboxes) {
+            side.accumulator = 0.0;
+            break;
+        }
+        side.accumulator -= params::TICK_TIME;
+    }
+    netplay::send(&mut side.session, &mut side.transport);
+}
+
+fn parse<T: std::str::FromStr>(arg: Option<&String>, default: T, what: &str) -> T {
+    return match arg {
+        Some(s) => match s.parse::<T>() {
+            Ok(v) => v,
+            Err(_) => {
+                eprintln!("{} must be a number: {}", what, s);
+                process::exit(2);
+            }
+        },
+        None => default,
+    };
+}
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut waves_dir = "html/data/waves".to_string();
+    if args.len() > 2 && args[1] == "--waves" {
+        waves_dir = args.remove(2);
+        args.remove(1);
+    }
+    if args.len() > 4 {
+        eprintln!(
+            "usage: {} [--waves DIR] [latency-ms] [loss-percent] [seconds]",
+            args[0]
+        );
+        process::exit(2);
+    }
+    let latency: f32 = parse(args.get(1), 80.0, "latency");
+    let loss: f32 = parse(args.get(2), 10.0, "loss");
+    let seconds: f32 = parse(args.get(3), 60.0, "seconds");
+
+    let hitboxes = assets::load_hitboxes();
+    let waves = Rc::new(wave::load_waves_from_dir(&waves_dir));
+    let seed = 1;
+    let ships = [ship::DEFAULT_SHIP, ship::DEFAULT_SHIP];
+    let (a, b) = netplay::loopback_pair(latency / 1000.0, loss / 100.0, seed);
+    let mut sides: Vec<Side> = Vec::new();
+    for (local, transport) in [a, b].into_iter().enumerate() {
+        let mut state = state::new_game_state(seed, &waves, &ships);
+        let session = netplay::new(local, &mut state);
+        sides.push(Side {
+            transport: transport,
+            session: session,
+            state: state,
+            accumulator: 0.0,
+            rng: rng::new(seed + 100 + local as u64),
+            held: input::Input::default(),
+        });
+    }
+
+    let mut time = 0.0;
+    while time < seconds {
+        for side in sides.iter_mut() {
+            frame(side, &hitboxes, usize::MAX);
+        }
+        time += FRAME_TIME;
+    }
+    //  let whoever's behind catch up, then let the last inputs get through
+    let ticks = sides.iter().map(|s| s.session.tick).max().unwrap_or(0);
+    let mut settle = 0;
+    while sides.iter().any(|s| s.session.confirmed_tick < ticks) && settle < 60 * 30 {
+        for side in sides.iter_mut() {
+            frame(side, &hitboxes, ticks);
+        }
+        settle += 1;
+    }
+
+    //  the same inputs, no network
+    let mut straight = state::new_game_state(seed, &waves, &ships);
+    straight.quiet = true;
+    for t in 0..ticks {
+        let inputs = [
+            sides[0].session.local_inputs[t],
+            sides[1].session.local_inputs[t],
+        ];
+        state::update_state(params::TICK_TIME, &mut straight, &inputs, &hitboxes);
+    }
+
+    let mut ok = true;
+    let expected = netplay::checksum(&straight);
+    println!("latency {} ms, loss {}%", latency, loss);
+    println!("ticks {}", ticks);
+    println!("score {} level {}", straight.score, straight.current_level);
+    for (ix, side) in sides.iter().enumerate() {
+        let s = &side.session;
+        println!(
+            "side {}: tick {} confirmed {} rollbacks {} ticks re-run {} desync {:?}",
+            ix, s.tick, s.confirmed_tick, s.rollbacks, s.ticks_rerun, s.desync_tick
+        );
+        let agrees = s.confirmed_tick == ticks
+            && s.tick == ticks
+            && netplay::checksum(&s.confirmed) == expected
+            && netplay::checksum(&side.state) == expected
+            && s.desync_tick.is_none();
+        ok = ok && agrees;
+    }
+    if !ok {
+        println!("MISMATCH");
+        process::exit(1);
+    }
+    println!("OK");
+}
@@ -0,0 +1,157 @@
+//  relay.rs
+//
+//  Pairs up versus players and passes their messages along; see versus.rs.
+//  It runs no game itself, so it's small enough to leave running on
+//  localhost while testing:
+//
+//  usage: relay
+//
+//  Desktop games connect over TCP on 8090; WebSocket clients can use 8091.
+//  Players are matched in the order they ask: the first to say Hello waits
+//  for the next who wants the same kind of game.
+
+#[cfg(not(target_arch = "wasm32"))]
+use quad_net::quad_socket::server;
+use space_observers::versus;
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Duration;
+
+#[derive(Default)]
+struct Relay {
+    next_id: usize,
+    waiting: HashMap<u8, usize>, //  by the kind of game wanted
+    partners: HashMap<usize, usize>,
+    //  messages for each connected client, sent on its next timer
+    outboxes: HashMap<usize, Vec<Vec<u8>>>,
+}
+
+#[derive(Default)]
+struct Client {
+    id: Option<usize>,
+}
+
+fn new_seed() -> u64 {
+    let since = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH);
+    return since.map(|d| d.as_nanos() as u64).unwrap_or(1);
+}
+
+fn post(relay: &mut Relay, to: usize, msg: &versus::Message) {
+    if let Some(out) = relay.outboxes.get_mut(&to) {
+        out.push(versus::encode(msg));
+    }
+}
+
+//  Leave any old match, then take on whoever is waiting for the same kind
+//  of game, or start waiting; whoever waited is player 1
+fn pair(relay: &mut Relay, id: usize, game: versus::Game) {
+    if let Some(old) = relay.partners.remove(&id) {
+        relay.partners.remove(&old);
+    }
+    relay.waiting.retain(|_, waiting| *waiting != id);
+    match relay.waiting.remove(&(game as u8)) {
+        Some(other) => {
+            relay.partners.insert(id, other);
+            relay.partners.insert(other, id);
+            let seed = new_seed();
+            post(relay, other, &versus::Message::Start(seed, 0));
+            post(relay, id, &versus::Message::Start(seed, 1));
+            println!("match: {} and {}, {:?}, seed {}", other, id, game, seed);
+        }
+        None => {
+            relay.waiting.insert(game as u8, id);
+        }
+    }
+}
+
+fn leave(relay: &mut Relay, id: usize) {
+    relay.outboxes.remove(&id);
+    relay.waiting.retain(|_, waiting| *waiting != id);
+    if let Some(other) = relay.partners.remove(&id) {
+        relay.partners.remove(&other);
+        post(relay, other, &versus::Message::OpponentLeft);
+    }
+    println!("{} left", id);
+}
+
+fn on_message(relay: &mut Relay, client: &mut Client, data: Vec<u8>) {
+    let id = match client.id {
+        Some(id) => id,
+        None => {
+            let id = relay.next_id;
+            relay.next_id += 1;
+            relay.outboxes.insert(id, Vec::new());
+            client.id = Some(id);
+            id
+        }
+    };
+    match versus::decode(&data) {
+        Ok(versus::Message::Hello(game)) => pair(relay, id, game),
+        _ => {
+            //  everything else is for the opponent, as it is
+            if let Some(&other) = relay.partners.get(&id) {
+                if let Some(out) = relay.outboxes.get_mut(&other) {
+                    out.push(data);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    let relay = Arc::new(Mutex::new(Relay::default()));
+    println!(
+        "relay listening on {} (tcp) and {} (websocket)",
+        versus::RELAY_TCP_ADDR,
+        versus::RELAY_WS_ADDR
+    );
+    server::listen(
+        versus::RELAY_TCP_ADDR,
+        versus::RELAY_WS_ADDR,
+        server::Settings {
+            on_message: {
+                let relay = relay.clone();
+                move |_out, client: &mut Client, data| {
+                    on_message(&mut relay.lock().unwrap(), client, data);
+                }
+            },
+            on_timer: {
+                let relay = relay.clone();
+                move |out, client: &Client| {
+                    let pending = match client.id {
+                        Some(id) => relay
+                            .lock()
+                            .unwrap()
+                            .outboxes
+                            .get_mut(&id)
+                            .map(|o| std::mem::take(o))
+                            .unwrap_or_default(),
+                        None => Vec::new(),
+                    };
+                    for data in pending {
+                        if out.send(&data).is_err() {
+                            break;
+                        }
+                    }
+                }
+            },
+            on_disconnect: {
+                let relay = relay.clone();
+                move |client: &Client| {
+                    if let Some(id) = client.id {
+                        leave(&mut relay.lock().unwrap(), id);
+                    }
+                }
+            },
+            timer: Some(Duration::from_millis(5)),
+            _marker: std::marker::PhantomData,
+        },
+    );
+}
+
+//  The relay is a desktop program; this keeps web builds of the crate going
+#[cfg(target_arch = "wasm32")]
+fn main() {}
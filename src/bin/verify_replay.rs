@@ -0,0 +1,84 @@
+//  verify_replay.rs
+//
+//  Re-simulates a replay headlessly and reports how the game really ended,
+//  so a leaderboard can accept only scores that the replay reproduces.
+//
+//  usage: verify_replay [--waves DIR] <file.replay> [claimed-score]
+//
+//  The claimed score defaults to the one stored in the replay. The wave
+//  files are part of the rules, so they must be the ones the game used;
+//  DIR defaults to html/data/waves. Exit status is 0 when the re-simulated
+//  score matches the claim, 1 when it does not, 2 when the replay can't be
+//  read or is malformed, and 3 when the replay runs out before the game is
+//  over, as a cut-short upload would; its score proves nothing.
+
+use space_observers::assets;
+use space_observers::replay;
+use space_observers::ship;
+use space_observers::state;
+use space_observers::wave;
+use std::process;
+use std::rc::Rc;
+
+fn main() {
+    let mut args: Vec<String> = std::env::args().collect();
+    let mut waves_dir = "html/data/waves".to_string();
+    if args.len() > 2 && args[1] == "--waves" {
+        waves_dir = args.remove(2);
+        args.remove(1);
+    }
+    if args.len() < 2 || args.len() > 3 {
+        eprintln!(
+            "usage: {} [--waves DIR] <file.replay> [claimed-score]",
+            args[0]
+        );
+        process::exit(2);
+    }
+
+    let rp = match replay::load_file(&args[1]) {
+        Ok(rp) => rp,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    let claimed = match args.get(2) {
+        Some(s) => match s.parse::<i32>() {
+            Ok(v) => v,
+            Err(_) => {
+                eprintln!("claimed score must be an integer: {}", s);
+                process::exit(2);
+            }
+        },
+        None => rp.score,
+    };
+
+    let hitboxes = assets::load_hitboxes();
+    let waves = Rc::new(wave::load_waves_from_dir(&waves_dir));
+    let st = replay::simulate(&rp, &hitboxes, &waves);
+    let game_over = state::is_game_over(&st);
+
+    println!("version {}", rp.version);
+    println!("seed {}", rp.seed);
+    println!(
+        "ship {} {}",
+        ship::hull(ship::sanitize(rp.ship)).name,
+        ship::COLOURS[ship::sanitize(rp.ship).colour]
+    );
+    println!("ticks {}", rp.inputs.len());
+    println!("score {}", st.score);
+    println!("level {}", st.current_level);
+    println!("lives {}", st.players[0].lives);
+    println!("game_over {}", game_over);
+    println!("claimed {}", claimed);
+
+    if !game_over {
+        println!("UNFINISHED");
+        process::exit(3);
+    }
+    if st.score != claimed {
+        println!("MISMATCH");
+        process::exit(1);
+    }
+    println!("OK");
+}
@@ -0,0 +1,177 @@
+//  boss.rs
+
+use crate::assets;
+use crate::explosion;
+use crate::rng;
+use crate::sprite;
+use crate::trig;
+
+//  Every BOSS_EVERY-th level is a boss fight instead of a formation
+pub const BOSS_EVERY: i32 = 4;
+const BOSS_Y: f32 = 0.32;
+const BASE_HEALTH: i32 = 30;
+const HEALTH_PER_BOSS: i32 = 15;
+const BASE_POINTS: i32 = 1000;
+const BOSS_FLASH_TIME: f32 = 0.1;
+const DYING_TIME: f32 = 2.5;
+const DYING_EXPLOSION_INTERVAL: f32 = 0.12;
+
+//  Bomb emitters, as offsets from the boss's centre: left wing, nose, right wing
+static EMITTERS: [(f32, f32); 3] = [(-0.06, 0.03), (0.0, 0.07), (0.06, 0.03)];
+
+//  The boss gets angrier as it loses health
+#[derive(PartialEq, Clone, Copy)]
+pub enum BossPhase {
+    Sweeping, //  side to side, bombing from the nose
+    Weaving,  //  figure-eights, alternating wing emitters
+    Frenzy,   //  fast swoops, every emitter at once
+}
+
+#[derive(Clone)]
+pub struct Boss {
+    pub sprite: &'static sprite::Sprite,
+    pub xpos: f32,
+    pub ypos: f32,
+    pub last_xpos: f32, //  used for render interpolation
+    pub last_ypos: f32,
+    pub health: i32,
+    pub max_health: i32,
+    pub points: i32,
+    pub clock: f32, //  drives the movement pattern
+    pub time_to_bomb: f32,
+    pub next_emitter: usize,
+    pub flash_timer: f32,
+    pub dying_timer: f32, //  > 0 while the death sequence plays
+    pub time_to_explosion: f32,
+    pub dead: bool,
+}
+
+pub fn is_boss_level(level: i32) -> bool {
+    return level > 0 && level % BOSS_EVERY == 0;
+}
+
+pub fn new(level: i32) -> Boss {
+    let nth = level / BOSS_EVERY - 1;
+    let health = BASE_HEALTH + HEALTH_PER_BOSS * nth;
+    return Boss {
+        sprite: &assets::BOSS_SPRITE,
+        xpos: 0.5,
+        ypos: BOSS_Y,
+        last_xpos: 0.5,
+        last_ypos: BOSS_Y,
+        health: health,
+        max_health: health,
+        points: BASE_POINTS * (nth + 1),
+        clock: 0.0,
+        time_to_bomb: 1.5,
+        next_emitter: 0,
+        flash_timer: 0.0,
+        dying_timer: 0.0,
+        time_to_explosion: 0.0,
+        dead: false,
+    };
+}
+
+pub fn phase_of(b: &Boss) -> BossPhase {
+    if b.health * 3 > b.max_health * 2 {
+        return BossPhase::Sweeping;
+    } else if b.health * 3 > b.max_health {
+        return BossPhase::Weaving;
+    }
+    return BossPhase::Frenzy;
+}
+
+pub fn is_dying(b: &Boss) -> bool {
+    return b.dying_timer > 0.0;
+}
+
+//  Returns true when this hit finished it off
+pub fn hit(b: &mut Boss) -> bool {
+    b.health -= 1;
+    b.flash_timer = BOSS_FLASH_TIME;
+    if b.health <= 0 {
+        b.health = 0;
+        b.dying_timer = DYING_TIME;
+        return true;
+    }
+    return false;
+}
+
+fn bomb_interval(phase: BossPhase) -> f32 {
+    return match phase {
+        BossPhase::Sweeping => 1.1,
+        BossPhase::Weaving => 0.8,
+        BossPhase::Frenzy => 0.9,
+    };
+}
+
+//  Moves the boss along its pattern. Returns the points bombs drop from this
+//  tick, if any. While dying, it stops where it is and goes up in a string of
+//  explosions instead.
+pub fn evolve(
+    dt: f32,
+    b: &mut Boss,
+    rng: &mut rng::Rng,
+    explosions: &mut Vec<explosion::Explosion>,
+) -> Vec<(f32, f32)> {
+    let mut drops: Vec<(f32, f32)> = Vec::new();
+    if b.flash_timer > 0.0 {
+        b.flash_timer -= dt;
+    }
+    if is_dying(b) {
+        b.dying_timer -= dt;
+        b.time_to_explosion -= dt;
+        if b.time_to_explosion <= 0.0 {
+            b.time_to_explosion += DYING_EXPLOSION_INTERVAL;
+            let hb = sprite::hitbox(b.sprite);
+            let x = b.xpos + rng::gen_range_f32(rng, -hb.f_w * 0.5, hb.f_w * 0.5);
+            let y = b.ypos + rng::gen_range_f32(rng, -hb.f_h * 0.5, hb.f_h * 0.5);
+            explosions.push(explosion::new(rng, x, y));
+        }
+        if b.dying_timer <= 0.0 {
+            //  one last big one
+            explosions.push(explosion::new_player(rng, b.xpos, b.ypos));
+            b.dead = true;
+        }
+        return drops;
+    }
+
+    let phase = phase_of(b);
+    let target_y = match phase {
+        BossPhase::Sweeping => {
+            b.clock += dt * 0.6;
+            BOSS_Y
+        }
+        BossPhase::Weaving => {
+            b.clock += dt * 0.8;
+            BOSS_Y + 0.08 * trig::sin(b.clock * 2.0)
+        }
+        BossPhase::Frenzy => {
+            b.clock += dt * 1.3;
+            let s = trig::sin(b.clock * 0.5);
+            BOSS_Y + 0.16 * s * s
+        }
+    };
+    b.xpos = 0.5 + 0.34 * trig::sin(b.clock);
+    //  ease vertically, so changing phase doesn't make it jump
+    b.ypos += (target_y - b.ypos) * (dt * 6.0).min(1.0);
+
+    b.time_to_bomb -= dt;
+    if b.time_to_bomb <= 0.0 {
+        b.time_to_bomb += bomb_interval(phase);
+        match phase {
+            BossPhase::Sweeping => drops.push(EMITTERS[1]),
+            BossPhase::Weaving => {
+                //  left wing, right wing, left wing, ...
+                drops.push(EMITTERS[b.next_emitter * 2]);
+                b.next_emitter = 1 - b.next_emitter;
+            }
+            BossPhase::Frenzy => drops.extend_from_slice(&EMITTERS),
+        }
+        for d in drops.iter_mut() {
+            d.0 += b.xpos;
+            d.1 += b.ypos;
+        }
+    }
+    return drops;
+}
@@ -0,0 +1,236 @@
+//  bunker.rs
+
+use macroquad::prelude as mq;
+
+//  Each bunker is a grid of cells that erode one hit at a time.
+pub const COLS: usize = 16;
+pub const ROWS: usize = 12;
+pub const CELL_SIZE: f32 = 0.007;
+pub const CELL_HEALTH: u8 = 4;
+
+const NUM_BUNKERS: usize = 4;
+const BUNKER_TOP: f32 = 1.12;
+
+#[derive(Clone)]
+pub struct Bunker {
+    pub xpos: f32, //  left edge
+    pub ypos: f32, //  top edge
+    pub cells: [u8; COLS * ROWS],
+}
+
+//  The classic shape: clipped top corners and an arch cut out of the bottom
+fn is_solid(col: usize, row: usize) -> bool {
+    let from_right = COLS - 1 - col;
+    if row < 3 && (col + row < 3 || from_right + row < 3) {
+        return false;
+    }
+    let from_center = (col as f32 - (COLS as f32 - 1.0) * 0.5).abs();
+    if row + 3 >= ROWS && from_center < 3.0 {
+        return false;
+    }
+    if row + 4 == ROWS && from_center < 2.0 {
+        return false;
+    }
+    return true;
+}
+
+pub fn new(xpos: f32, ypos: f32) -> Bunker {
+    let mut cells = [0u8; COLS * ROWS];
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            if is_solid(col, row) {
+                cells[row * COLS + col] = CELL_HEALTH;
+            }
+        }
+    }
+    return Bunker {
+        xpos: xpos,
+        ypos: ypos,
+        cells: cells,
+    };
+}
+
+//  Four bunkers spaced evenly between the player and the aliens
+pub fn new_row() -> Vec<Bunker> {
+    let width = COLS as f32 * CELL_SIZE;
+    let spacing = 1.0 / NUM_BUNKERS as f32;
+    let mut ret: Vec<Bunker> = Vec::new();
+    for ix in 0..NUM_BUNKERS {
+        let center = spacing * (ix as f32 + 0.5);
+        ret.push(new(center - width * 0.5, BUNKER_TOP));
+    }
+    return ret;
+}
+
+//  Range of cells overlapped by a box, or None if the box misses the bunker
+fn cell_range(
+    b: &Bunker,
+    left: f32,
+    right: f32,
+    top: f32,
+    bottom: f32,
+) -> Option<(usize, usize, usize, usize)> {
+    let bw = COLS as f32 * CELL_SIZE;
+    let bh = ROWS as f32 * CELL_SIZE;
+    if right <= b.xpos || left >= b.xpos + bw || bottom <= b.ypos || top >= b.ypos + bh {
+        return None;
+    }
+    let c0 = (((left - b.xpos) / CELL_SIZE).max(0.0) as usize).min(COLS - 1);
+    let c1 = (((right - b.xpos) / CELL_SIZE).max(0.0) as usize).min(COLS - 1);
+    let r0 = (((top - b.ypos) / CELL_SIZE).max(0.0) as usize).min(ROWS - 1);
+    let r1 = (((bottom - b.ypos) / CELL_SIZE).max(0.0) as usize).min(ROWS - 1);
+    return Some((c0, c1, r0, r1));
+}
+
+fn damage_cell(b: &mut Bunker, col: i32, row: i32, amount: u8) {
+    if col < 0 || row < 0 || col >= COLS as i32 || row >= ROWS as i32 {
+        return;
+    }
+    let ix = row as usize * COLS + col as usize;
+    b.cells[ix] = b.cells[ix].saturating_sub(amount);
+}
+
+//  A projectile box hitting the bunker chips the first live cell it meets,
+//  and a little of the cells around it. Bullets travel up, so they meet the
+//  lowest cell first; bombs travel down and meet the highest.
+//  Returns true if the projectile was stopped.
+pub fn hit(b: &mut Bunker, left: f32, right: f32, top: f32, bottom: f32, upward: bool) -> bool {
+    let (c0, c1, r0, r1) = match cell_range(b, left, right, top, bottom) {
+        Some(r) => r,
+        None => return false,
+    };
+    let rows: Vec<usize> = if upward {
+        (r0..=r1).rev().collect()
+    } else {
+        (r0..=r1).collect()
+    };
+    for row in rows {
+        for col in c0..=c1 {
+            if b.cells[row * COLS + col] > 0 {
+                let (c, r) = (col as i32, row as i32);
+                damage_cell(b, c, r, CELL_HEALTH / 2 + 1);
+                damage_cell(b, c - 1, r, 1);
+                damage_cell(b, c + 1, r, 1);
+                damage_cell(b, c, r - 1, 1);
+                damage_cell(b, c, r + 1, 1);
+                return true;
+            }
+        }
+    }
+    return false;
+}
+
+//  Aliens marching through a bunker simply wipe out what they touch
+pub fn erase(b: &mut Bunker, left: f32, right: f32, top: f32, bottom: f32) {
+    if let Some((c0, c1, r0, r1)) = cell_range(b, left, right, top, bottom) {
+        for row in r0..=r1 {
+            for col in c0..=c1 {
+                b.cells[row * COLS + col] = 0;
+            }
+        }
+    }
+}
+
+pub fn render(left: f32, top: f32, width: f32, b: &Bunker) {
+    let sz = width * CELL_SIZE;
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let health = b.cells[row * COLS + col];
+            if health == 0 {
+                continue;
+            }
+            let alpha = health as f32 / CELL_HEALTH as f32;
+            mq::draw_rectangle(
+                left + width * (b.xpos + col as f32 * CELL_SIZE),
+                top + width * (b.ypos + row as f32 * CELL_SIZE),
+                sz.ceil(),
+                sz.ceil(),
+                mq::Color::new(0.3, 0.9, 0.35, 0.25 + 0.75 * alpha),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cell(b: &Bunker, col: usize, row: usize) -> u8 {
+        return b.cells[row * COLS + col];
+    }
+
+    //  A box a little narrower than one column, the bunker's full height
+    fn column_box(b: &Bunker, col: usize) -> (f32, f32, f32, f32) {
+        let left = b.xpos + col as f32 * CELL_SIZE + CELL_SIZE * 0.2;
+        let top = b.ypos + CELL_SIZE * 0.2;
+        return (
+            left,
+            left + CELL_SIZE * 0.6,
+            top,
+            top + (ROWS as f32 - 0.4) * CELL_SIZE,
+        );
+    }
+
+    #[test]
+    fn missing_leaves_it_alone() {
+        let mut b = new(0.5, 1.0);
+        let before = b.cells;
+        assert!(!hit(&mut b, 0.1, 0.11, 1.0, 1.05, true));
+        assert!(!hit(&mut b, 0.6, 0.61, 0.5, 0.55, false));
+        erase(&mut b, 0.1, 0.2, 0.5, 0.6);
+        assert!(b.cells == before);
+    }
+
+    #[test]
+    fn bullets_hit_the_bottom_and_bombs_the_top() {
+        //  column 1 has its corner clipped off: rows 2 and down are solid
+        let mut b = new(0.5, 1.0);
+        let (l, r, t, btm) = column_box(&b, 1);
+        assert_eq!(cell(&b, 1, 1), 0);
+        assert!(hit(&mut b, l, r, t, btm, true));
+        assert!(cell(&b, 1, ROWS - 1) < CELL_HEALTH);
+        assert_eq!(cell(&b, 1, 2), CELL_HEALTH);
+        assert!(hit(&mut b, l, r, t, btm, false));
+        assert!(cell(&b, 1, 2) < CELL_HEALTH);
+        assert_eq!(cell(&b, 1, ROWS / 2), CELL_HEALTH);
+    }
+
+    #[test]
+    fn enough_hits_bore_right_through() {
+        let mut b = new(0.5, 1.0);
+        let (l, r, t, btm) = column_box(&b, 5);
+        let mut hits = 0;
+        while hit(&mut b, l, r, t, btm, true) {
+            hits += 1;
+            assert!(hits < ROWS * CELL_HEALTH as usize, "never bored through");
+        }
+        for row in 0..ROWS {
+            assert_eq!(cell(&b, 5, row), 0, "row {}", row);
+        }
+        //  the neighbours are chipped, not gone
+        assert!((0..ROWS).any(|row| cell(&b, 4, row) > 0));
+    }
+
+    #[test]
+    fn erase_wipes_out_just_what_it_covers() {
+        let mut b = new(0.5, 1.0);
+        let before = b.cells;
+        //  the middle four by four cells
+        let left = b.xpos + 6.2 * CELL_SIZE;
+        let top = b.ypos + 4.2 * CELL_SIZE;
+        erase(
+            &mut b,
+            left,
+            left + 3.6 * CELL_SIZE,
+            top,
+            top + 3.6 * CELL_SIZE,
+        );
+        for row in 0..ROWS {
+            for col in 0..COLS {
+                let inside = (6..10).contains(&col) && (4..8).contains(&row);
+                let expected = if inside { 0 } else { before[row * COLS + col] };
+                assert_eq!(cell(&b, col, row), expected, "col {} row {}", col, row);
+            }
+        }
+    }
+}
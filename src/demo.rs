@@ -0,0 +1,95 @@
+//  demo.rs
+
+use crate::input;
+use crate::params;
+use crate::state;
+use crate::ufo;
+
+//  The attract mode's player. It isn't good, just good enough to look like
+//  it knows what it's doing: it dodges whatever is about to land on it and
+//  otherwise lines up under something to shoot. Like a person, it only
+//  looks at what's on screen and plays through an Input.
+
+const SHIP_Y: f32 = 1.333 - 0.06;
+//  How close something falling has to get before it's worth dodging
+const DANGER_HEIGHT: f32 = 0.3;
+const DANGER_WIDTH: f32 = 0.06;
+//  Near enough to be lined up under a target
+const AIM_SLACK: f32 = 0.015;
+
+//  What's coming down on the ship: (x, y) of the lowest thing in the way
+fn threat(state: &state::State, x: f32) -> Option<(f32, f32)> {
+    let bombs = state
+        .bombs
+        .iter()
+        .filter(|b| !b.dead)
+        .map(|b| (b.xpos, b.ypos));
+    let divers = state
+        .aliens
+        .iter()
+        .filter(|a| !a.dead && a.dive.is_some())
+        .map(|a| (a.xpos, a.ypos));
+    let meteors = state
+        .meteors
+        .iter()
+        .filter(|m| !m.dead)
+        .map(|m| (m.xpos, m.ypos));
+    return bombs
+        .chain(divers)
+        .chain(meteors)
+        .filter(|(tx, ty)| {
+            *ty < SHIP_Y + 0.02 && SHIP_Y - *ty < DANGER_HEIGHT && (tx - x).abs() < DANGER_WIDTH
+        })
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+}
+
+//  The UFO while it's worth points, then the boss, then an alien
+fn target(state: &state::State, x: f32) -> Option<f32> {
+    if let Some(u) = &state.ufo {
+        if !u.dead && !ufo::is_shown_as_points(u) && u.xpos > 0.0 && u.xpos < 1.0 {
+            return Some(u.xpos);
+        }
+    }
+    if let Some(b) = &state.boss {
+        return Some(b.xpos);
+    }
+    //  the lower an alien, the sooner it lands, so the more it's worth
+    //  going out of the way for
+    let cost = |a: &&state::Alien| (a.xpos - x).abs() - a.ypos;
+    return state
+        .aliens
+        .iter()
+        .filter(|a| !a.dead)
+        .min_by(|a, b| cost(a).total_cmp(&cost(b)))
+        .map(|a| a.xpos);
+}
+
+pub fn play(state: &state::State) -> input::Input {
+    let mut ret = input::Input::default();
+    let x = state.players[0].pos_fr;
+
+    if let Some((tx, _)) = threat(state, x) {
+        //  step aside, away from the wall if it's right overhead
+        let go_left = if (tx - x).abs() < 0.005 {
+            x > 0.5
+        } else {
+            tx > x
+        };
+        let blocked = if go_left {
+            x <= params::LEFT_MARGIN + 0.01
+        } else {
+            x >= params::RIGHT_MARGIN - 0.01
+        };
+        ret.left = go_left != blocked;
+        ret.right = go_left == blocked;
+        ret.fire = true;
+        return ret;
+    }
+
+    if let Some(tx) = target(state, x) {
+        ret.left = tx < x - AIM_SLACK;
+        ret.right = tx > x + AIM_SLACK;
+        ret.fire = (tx - x).abs() < AIM_SLACK * 3.0;
+    }
+    return ret;
+}
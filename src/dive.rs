@@ -0,0 +1,112 @@
+//  dive.rs
+
+use crate::rng;
+
+//  A diving alien leaves its slot in the formation, swoops down at the
+//  player along one curve and climbs back along another. The slot keeps
+//  marching with the formation while it's away, so the way back aims at
+//  wherever the slot has got to.
+
+pub const MAX_DIVERS: usize = 3;
+const DIVE_TIME: f32 = 2.2;
+const RETURN_TIME: f32 = 2.0;
+const DIVE_BOTTOM: f32 = 1.25;
+const FIRE_MIN_TIME: f32 = 0.4;
+const FIRE_EXTRA_TIME: f32 = 0.8;
+
+#[derive(Clone)]
+pub struct Dive {
+    pub slot_x: f32, //  where the formation would have this alien now
+    pub slot_y: f32,
+    pub clock: f32, //  0 to 1 along the current leg
+    pub returning: bool,
+    pub side: f32, //  -1 peels off to the left, 1 to the right
+    pub start_x: f32,
+    pub start_y: f32,
+    pub target_x: f32, //  where the player was when the dive started
+    pub time_to_fire: f32,
+    pub done: bool,
+}
+
+pub fn time_to_next(rng: &mut rng::Rng, interval: f32) -> f32 {
+    return rng::gen_range_f32(rng, interval * 0.5, interval * 1.5);
+}
+
+pub fn new(rng: &mut rng::Rng, slot_x: f32, slot_y: f32, player_x: f32) -> Dive {
+    //  aliens on the left of the player curl out to the left first, and
+    //  the other way round, so the swoop crosses in front of the player
+    let side = if slot_x < player_x { -1.0 } else { 1.0 };
+    return Dive {
+        slot_x: slot_x,
+        slot_y: slot_y,
+        clock: 0.0,
+        returning: false,
+        side: side,
+        start_x: slot_x,
+        start_y: slot_y,
+        target_x: player_x,
+        time_to_fire: rng::gen_range_f32(rng, FIRE_MIN_TIME, FIRE_MIN_TIME + FIRE_EXTRA_TIME),
+        done: false,
+    };
+}
+
+fn bezier(p0: f32, p1: f32, p2: f32, p3: f32, t: f32) -> f32 {
+    let u = 1.0 - t;
+    return u * u * u * p0 + 3.0 * u * u * t * p1 + 3.0 * u * t * t * p2 + t * t * t * p3;
+}
+
+//  The formation moved; so did the slot
+pub fn shift_slot(d: &mut Dive, dx: f32, dy: f32) {
+    d.slot_x += dx;
+    d.slot_y += dy;
+}
+
+pub fn position(d: &Dive) -> (f32, f32) {
+    if !d.returning {
+        //  loop up and out, then swing down across the player's position
+        let x = bezier(
+            d.start_x,
+            d.start_x + d.side * 0.15,
+            d.target_x - d.side * 0.25,
+            d.target_x,
+            d.clock,
+        );
+        let y = bezier(d.start_y, d.start_y - 0.1, 0.9, DIVE_BOTTOM, d.clock);
+        return (x, y);
+    }
+    //  pull out of the dive and climb back into the slot
+    let x = bezier(
+        d.target_x,
+        d.target_x + d.side * 0.3,
+        d.slot_x + d.side * 0.1,
+        d.slot_x,
+        d.clock,
+    );
+    let y = bezier(DIVE_BOTTOM, 1.3, d.slot_y + 0.3, d.slot_y, d.clock);
+    return (x, y);
+}
+
+//  Returns true when the diver fires this tick; it only fires on the way down
+pub fn evolve(dt: f32, d: &mut Dive, rng: &mut rng::Rng) -> bool {
+    let mut fire = false;
+    if !d.returning {
+        d.clock += dt / DIVE_TIME;
+        d.time_to_fire -= dt;
+        if d.time_to_fire <= 0.0 {
+            d.time_to_fire +=
+                rng::gen_range_f32(rng, FIRE_MIN_TIME, FIRE_MIN_TIME + FIRE_EXTRA_TIME);
+            fire = true;
+        }
+        if d.clock >= 1.0 {
+            d.clock = 0.0;
+            d.returning = true;
+        }
+    } else {
+        d.clock += dt / RETURN_TIME;
+        if d.clock >= 1.0 {
+            d.clock = 1.0;
+            d.done = true;
+        }
+    }
+    return fire;
+}
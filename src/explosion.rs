@@ -1,15 +1,17 @@
 //  explosion.rs
 
 use crate::assets;
+use crate::rng;
 use crate::sprite;
-use macroquad::rand;
 
+#[derive(Clone)]
 pub struct Explosion {
     pub sprite: &'static sprite::Sprite,
     pub xpos: f32,
     pub ypos: f32,
     pub speed: f32,
     pub phase: f32,
+    pub scale: f32,
     pub sound: usize,
     pub fresh: bool, //  used for sound
     pub growing: bool,
@@ -17,24 +19,33 @@ pub struct Explosion {
 }
 
 //  Explosion for an alien -- small "zip" sound effect
-pub fn new(xpos: f32, ypos: f32) -> Explosion {
-    let six: usize = rand::gen_range(0, 4) + assets::IX_SOUND_POP_01;
-    return new_sound(xpos, ypos, six);
+pub fn new(rng: &mut rng::Rng, xpos: f32, ypos: f32) -> Explosion {
+    let six: usize = rng::gen_range_usize(rng, 0, 4) + assets::IX_SOUND_POP_01;
+    return new_sound(rng, xpos, ypos, six);
 }
 
 //  Explosion for player -- bit "boom" sound effect
-pub fn new_player(xpos: f32, ypos: f32) -> Explosion {
-    return new_sound(xpos, ypos, assets::IX_SOUND_EXPLOSION);
+pub fn new_player(rng: &mut rng::Rng, xpos: f32, ypos: f32) -> Explosion {
+    return new_sound(rng, xpos, ypos, assets::IX_SOUND_EXPLOSION);
 }
 
-pub fn new_sound(xpos: f32, ypos: f32, sound: usize) -> Explosion {
-    let ix: usize = rand::gen_range(0, 9);
+//  Explosion for two shots colliding -- a little puff
+pub fn new_small(rng: &mut rng::Rng, xpos: f32, ypos: f32) -> Explosion {
+    let mut ret = new(rng, xpos, ypos);
+    ret.scale = 0.35;
+    ret.speed = 6.0;
+    return ret;
+}
+
+pub fn new_sound(rng: &mut rng::Rng, xpos: f32, ypos: f32, sound: usize) -> Explosion {
+    let ix: usize = rng::gen_range_usize(rng, 0, 9);
     return Explosion {
         sprite: &assets::EXPLOSION_SPRITES[ix],
         xpos: xpos,
         ypos: ypos,
         speed: 4.0,
         phase: 0.2,
+        scale: 1.0,
         sound: sound,
         fresh: true,
         growing: true,
@@ -63,6 +74,6 @@ pub fn render(left: f32, top: f32, width: f32, x: &Explosion, ass: &assets::Asse
         x.xpos,
         x.ypos,
         0.0,
-        x.phase,
+        x.phase * x.scale,
     );
 }
@@ -0,0 +1,88 @@
+//  ghost.rs
+
+use crate::params;
+use crate::replay;
+use crate::sprite;
+use crate::state;
+use crate::wave;
+use std::rc::Rc;
+
+//  A past run flown alongside the live game, to race against: the personal
+//  best, or the top run from the leaderboard. The live game is started on
+//  the ghost's seed, so both face the same aliens, and the ghost's game is
+//  re-run from its recorded inputs one tick for every live tick. It's only
+//  ever drawn, never collided with.
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Source {
+    Best, //  this player's own, see replay::save()
+    Top,  //  downloaded, see replay::load_top()
+}
+
+pub struct Ghost {
+    pub source: Source,
+    pub replay: replay::Replay,
+    pub state: state::State,
+    pub tick: usize,
+}
+
+pub fn label(source: Source) -> &'static str {
+    return match source {
+        Source::Best => "Best",
+        Source::Top => "Top",
+    };
+}
+
+fn load(source: Source) -> Option<replay::Replay> {
+    return match source {
+        Source::Best => replay::load_best(),
+        Source::Top => replay::load_top(),
+    };
+}
+
+fn ghost_state(replay: &replay::Replay, waves: &Rc<Vec<wave::Wave>>) -> state::State {
+    let mut st = state::new_game_state(replay.seed, waves, &[replay.ship]);
+    st.quiet = true;
+    return st;
+}
+
+pub fn new(source: Source, waves: &Rc<Vec<wave::Wave>>) -> Option<Ghost> {
+    let replay = load(source)?;
+    let st = ghost_state(&replay, waves);
+    return Some(Ghost {
+        source: source,
+        replay: replay,
+        state: st,
+        tick: 0,
+    });
+}
+
+//  Off, then the best run, then the top one, skipping any there isn't
+pub fn cycle(current: Option<Ghost>, waves: &Rc<Vec<wave::Wave>>) -> Option<Ghost> {
+    let after_best = || new(Source::Top, waves);
+    return match current.map(|g| g.source) {
+        None => new(Source::Best, waves).or_else(after_best),
+        Some(Source::Best) => after_best(),
+        Some(Source::Top) => None,
+    };
+}
+
+//  Back to the start, for the next race; a best run beaten since is the
+//  one raced now
+pub fn restart(ghost: &mut Ghost, waves: &Rc<Vec<wave::Wave>>) {
+    if let Some(replay) = load(ghost.source) {
+        ghost.replay = replay;
+    }
+    ghost.state = ghost_state(&ghost.replay, waves);
+    ghost.tick = 0;
+}
+
+//  One tick, as long as the run lasts
+pub fn step(ghost: &mut Ghost, hitboxes: &[sprite::Hitbox]) {
+    if ghost.tick >= ghost.replay.inputs.len() || state::is_game_over(&ghost.state) {
+        return;
+    }
+    let input = ghost.replay.inputs[ghost.tick];
+    state::update_state(params::TICK_TIME, &mut ghost.state, &[input], hitboxes);
+    ghost.tick += 1;
+}
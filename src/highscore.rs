@@ -1,22 +1,33 @@
 //  highscore.rs
 
-use sapp_jsutils::JsObject;
+use crate::js::JsObject;
 use std::fmt;
 
 #[derive(PartialEq)]
 pub struct Score {
-    name: String,
-    score: i32,
+    pub name: String,
+    pub score: i32,
 }
 
+#[cfg(target_arch = "wasm32")]
 extern "C" {
     fn register_highscore(arg: JsObject);
     fn read_highscores() -> JsObject;
 }
 
-pub fn register(score: i32) {
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn register_highscore(_arg: JsObject) {}
+
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn read_highscores() -> JsObject {
+    return JsObject::object();
+}
+
+//  An empty name means whoever is logged in
+pub fn register(name: &str, score: i32) {
     let obj = JsObject::object();
     //  Include game parameters
+    obj.set_field_string("name", name);
     obj.set_field_f32("score", score as f32);
     unsafe {
         register_highscore(obj);
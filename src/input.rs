@@ -0,0 +1,110 @@
+//  input.rs
+
+use macroquad::prelude as mq;
+
+//  One frame's worth of player input. These are "is the key held" levels;
+//  the simulation detects presses itself by comparing against the previous
+//  frame, so an input stream can be fed back in without a keyboard.
+#[derive(Clone, Copy, Default, PartialEq)]
+pub struct Input {
+    pub left: bool,
+    pub right: bool,
+    pub fire: bool,
+    pub pause: bool,
+}
+
+pub fn read_keyboard() -> Input {
+    return Input {
+        left: mq::is_key_down(mq::KeyCode::Left) || mq::is_key_down(mq::KeyCode::A),
+        right: mq::is_key_down(mq::KeyCode::Right) || mq::is_key_down(mq::KeyCode::D),
+        fire: mq::is_key_down(mq::KeyCode::Space),
+        pause: mq::is_key_down(mq::KeyCode::Escape),
+    };
+}
+
+//  Two players sharing the keyboard: arrows and Space, and A/D and W
+pub fn read_keyboard_split() -> [Input; 2] {
+    return [
+        Input {
+            left: mq::is_key_down(mq::KeyCode::Left),
+            right: mq::is_key_down(mq::KeyCode::Right),
+            fire: mq::is_key_down(mq::KeyCode::Space),
+            pause: mq::is_key_down(mq::KeyCode::Escape),
+        },
+        Input {
+            left: mq::is_key_down(mq::KeyCode::A),
+            right: mq::is_key_down(mq::KeyCode::D),
+            fire: mq::is_key_down(mq::KeyCode::W),
+            pause: false,
+        },
+    ];
+}
+
+//  Gamepads come from the browser's Gamepad API, as to_bits() would pack
+//  them; 0 for a pad that isn't there. macroquad has no gamepad support of
+//  its own, so natively there are none.
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn read_gamepad(ix: u32) -> u32;
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn read_gamepad(_ix: u32) -> u32 {
+    return 0;
+}
+
+fn read_gamepad_input(ix: usize) -> Input {
+    let bits = unsafe { read_gamepad(ix as u32) };
+    return from_bits(bits as u8);
+}
+
+fn either(a: Input, b: Input) -> Input {
+    return Input {
+        left: a.left || b.left,
+        right: a.right || b.right,
+        fire: a.fire || b.fire,
+        pause: a.pause || b.pause,
+    };
+}
+
+//  One Input per player; gamepad N works alongside player N's keys
+pub fn read_players(count: usize) -> Vec<Input> {
+    if count < 2 {
+        return vec![either(read_keyboard(), read_gamepad_input(0))];
+    }
+    let keys = read_keyboard_split();
+    return (0..count)
+        .map(|ix| either(keys[ix.min(1)], read_gamepad_input(ix)))
+        .collect();
+}
+
+const BIT_LEFT: u8 = 1;
+const BIT_RIGHT: u8 = 2;
+const BIT_FIRE: u8 = 4;
+const BIT_PAUSE: u8 = 8;
+
+pub fn to_bits(input: &Input) -> u8 {
+    let mut bits = 0;
+    if input.left {
+        bits |= BIT_LEFT;
+    }
+    if input.right {
+        bits |= BIT_RIGHT;
+    }
+    if input.fire {
+        bits |= BIT_FIRE;
+    }
+    if input.pause {
+        bits |= BIT_PAUSE;
+    }
+    return bits;
+}
+
+pub fn from_bits(bits: u8) -> Input {
+    return Input {
+        left: bits & BIT_LEFT != 0,
+        right: bits & BIT_RIGHT != 0,
+        fire: bits & BIT_FIRE != 0,
+        pause: bits & BIT_PAUSE != 0,
+    };
+}
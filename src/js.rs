@@ -0,0 +1,36 @@
+//  js.rs
+
+//  JsObject only has a backing implementation inside the browser. Native
+//  builds (headless simulation, tests, tools) get an inert stand-in with the
+//  same surface, so telemetry and highscore calls compile to nothing.
+
+#[cfg(target_arch = "wasm32")]
+pub use sapp_jsutils::JsObject;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub struct JsObject;
+
+#[cfg(not(target_arch = "wasm32"))]
+impl JsObject {
+    pub fn string(_string: &str) -> JsObject {
+        return JsObject;
+    }
+
+    pub fn object() -> JsObject {
+        return JsObject;
+    }
+
+    pub fn to_string(&self, _buf: &mut String) {}
+
+    pub fn field(&self, _field: &str) -> JsObject {
+        return JsObject;
+    }
+
+    pub fn field_u32(&self, _field: &str) -> u32 {
+        return 0;
+    }
+
+    pub fn set_field_f32(&self, _field: &str, _data: f32) {}
+
+    pub fn set_field_string(&self, _field: &str, _data: &str) {}
+}
@@ -0,0 +1,94 @@
+//  levels.rs
+
+//  The difficulty curve, one row per wave. Waves past the end of the table
+//  keep using the last row.
+pub struct LevelParams {
+    pub start_y: f32,         //  height of the top row of the formation
+    pub march_speed: f32,     //  multiplier on HORIZ_SPEED/VERT_SPEED
+    pub bomb_speed: f32,      //  how fast bombs fall
+    pub bomb_min_time: f32,   //  shortest gap between bombs
+    pub bomb_extra_time: f32, //  random extra gap between bombs, up to this
+    pub firing_duration: f32, //  player's cooldown between shots
+    pub target_bias: f32,     //  chance a bomb comes from the column nearest the player
+}
+
+static LEVELS: [LevelParams; 8] = [
+    LevelParams {
+        start_y: 0.20,
+        march_speed: 1.0,
+        bomb_speed: 0.60,
+        bomb_min_time: 0.70,
+        bomb_extra_time: 2.50,
+        firing_duration: 0.80,
+        target_bias: 0.10,
+    },
+    LevelParams {
+        start_y: 0.24,
+        march_speed: 1.2,
+        bomb_speed: 0.64,
+        bomb_min_time: 0.66,
+        bomb_extra_time: 2.00,
+        firing_duration: 0.78,
+        target_bias: 0.15,
+    },
+    LevelParams {
+        start_y: 0.28,
+        march_speed: 1.4,
+        bomb_speed: 0.68,
+        bomb_min_time: 0.62,
+        bomb_extra_time: 1.67,
+        firing_duration: 0.76,
+        target_bias: 0.20,
+    },
+    LevelParams {
+        start_y: 0.32,
+        march_speed: 1.6,
+        bomb_speed: 0.72,
+        bomb_min_time: 0.58,
+        bomb_extra_time: 1.43,
+        firing_duration: 0.74,
+        target_bias: 0.28,
+    },
+    LevelParams {
+        start_y: 0.36,
+        march_speed: 1.8,
+        bomb_speed: 0.76,
+        bomb_min_time: 0.54,
+        bomb_extra_time: 1.25,
+        firing_duration: 0.72,
+        target_bias: 0.36,
+    },
+    LevelParams {
+        start_y: 0.38,
+        march_speed: 2.0,
+        bomb_speed: 0.80,
+        bomb_min_time: 0.50,
+        bomb_extra_time: 1.11,
+        firing_duration: 0.70,
+        target_bias: 0.44,
+    },
+    LevelParams {
+        start_y: 0.40,
+        march_speed: 2.2,
+        bomb_speed: 0.84,
+        bomb_min_time: 0.47,
+        bomb_extra_time: 1.00,
+        firing_duration: 0.68,
+        target_bias: 0.52,
+    },
+    LevelParams {
+        start_y: 0.42,
+        march_speed: 2.4,
+        bomb_speed: 0.88,
+        bomb_min_time: 0.44,
+        bomb_extra_time: 0.91,
+        firing_duration: 0.66,
+        target_bias: 0.60,
+    },
+];
+
+//  Levels count from 1
+pub fn for_level(level: i32) -> &'static LevelParams {
+    let ix = (level - 1).clamp(0, LEVELS.len() as i32 - 1) as usize;
+    return &LEVELS[ix];
+}
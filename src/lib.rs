@@ -0,0 +1,30 @@
+//  lib.rs
+//  The game proper; shared by the game binary and the native tools in src/bin
+
+pub mod assets;
+pub mod boss;
+pub mod bunker;
+pub mod demo;
+pub mod dive;
+pub mod explosion;
+pub mod ghost;
+pub mod highscore;
+pub mod input;
+pub mod js;
+pub mod levels;
+pub mod meteor;
+pub mod netplay;
+pub mod params;
+pub mod powerup;
+pub mod replay;
+pub mod rng;
+pub mod ship;
+pub mod sounds;
+pub mod sprite;
+pub mod state;
+pub mod telemetry;
+pub mod trig;
+pub mod turns;
+pub mod ufo;
+pub mod versus;
+pub mod wave;
@@ -1,52 +1,1098 @@
 use macroquad::prelude as mq;
-mod assets;
-mod explosion;
-mod highscore;
-mod params;
-mod sounds;
-mod sprite;
-mod state;
-mod telemetry;
+use space_observers::assets;
+use space_observers::boss;
+use space_observers::bunker;
+use space_observers::demo;
+use space_observers::explosion;
+use space_observers::ghost;
+use space_observers::highscore;
+use space_observers::input;
+use space_observers::meteor;
+use space_observers::netplay;
+use space_observers::params;
+use space_observers::powerup;
+use space_observers::replay;
+use space_observers::ship;
+use space_observers::sounds;
+use space_observers::sprite;
+use space_observers::state;
+use space_observers::telemetry;
+use space_observers::turns;
+use space_observers::ufo;
+use space_observers::versus;
+use space_observers::wave;
+use std::rc::Rc;
 
 /*
 
 TODO:
 
-- increment pace for each reset
-- alien bombs
-- bottom shields
-- top UFO
 - highscores
 
 */
 
 #[macroquad::main("Space Observers")]
 async fn main() {
-    telemetry::tele_startup();
-
     draw_intro().await;
 
     let assets = assets::load_assets().await;
-
-    let mut state = state::new_game_state();
+    let waves = Rc::new(wave::load_waves().await);
 
     telemetry::tele_loading_done();
 
-    //  make sure frame time isn't vastly off the first time it runs
-    mq::next_frame().await;
-    mq::next_frame().await;
+    //  when watching a replay, its inputs drive the game instead of the keyboard
+    let mut playback: Option<Playback> = None;
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = std::env::args().nth(1) {
+        match replay::load_file(&path) {
+            Ok(r) => playback = Some(Playback { replay: r, tick: 0 }),
+            Err(e) => mq::warn!("{}", e),
+        }
+    }
+
+    //  the start event carries the first ship picked, so it waits for that
+    let mut started = false;
+
+    //  the attract mode hands back to here, as on the arcade cabinet
+    loop {
+        //  one name per player; a replay from the command line is one player
+        let (mut mode, names) = match &playback {
+            Some(_) => (Mode::Solo, vec![String::new()]),
+            None => intro_screen().await,
+        };
+        let mut ships = match &playback {
+            Some(pb) => vec![pb.replay.ship],
+            None => select_ships(&assets, &names, &[]).await,
+        };
+        if !started {
+            let first = ship::sanitize(ships[0]);
+            telemetry::tele_startup(ship::hull(first).name, ship::COLOURS[first.colour]);
+            started = true;
+        }
+
+        //  a network game that can't reach the relay is played alone instead
+        let mut versus: Option<versus::Versus> = None;
+        let mut online: Option<netplay::Transport> = None;
+        //  a past run to race, picked on the game over screen
+        let mut ghost: Option<ghost::Ghost> = None;
+        let mut connected = match mode {
+            Mode::Versus => versus::connect().map(|v| versus = Some(v)),
+            Mode::Online => netplay::connect().map(|t| online = Some(t)),
+            _ => Ok(()),
+        };
+        if connected.is_ok() {
+            connected = wait_for_relay(&versus, &online).await;
+        }
+        if let Err(e) = connected {
+            show_message(&e).await;
+            mode = Mode::Solo;
+            versus = None;
+            online = None;
+        }
+
+        let (mut state, mut turns, mut session) = match &playback {
+            Some(pb) => (
+                state::new_game_state(pb.replay.seed, &waves, &[pb.replay.ship]),
+                None,
+                None,
+            ),
+            None => {
+                start_game(
+                    &waves,
+                    &mut mode,
+                    &names,
+                    &ships,
+                    &mut versus,
+                    &mut online,
+                    &mut ghost,
+                )
+                .await
+            }
+        };
+        //  only one-player games are recorded
+        let mut recording = replay::new(state.seed, ships[0]);
+        let mut recording_saved = mode != Mode::Solo;
+
+        //  while this counts down, the game holds for the next player to get ready
+        let mut turn_banner = 0.0;
+
+        //  make sure frame time isn't vastly off the first time it runs
+        mq::next_frame().await;
+        mq::next_frame().await;
+
+        //  simulation time not yet stepped, always less than one tick
+        let mut accumulator = 0.0;
+        //  how long the game over screen has sat untouched
+        let mut idle_time = 0.0;
+
+        loop {
+            let delta_time = mq::get_frame_time().min(0.1);
+
+            if state::is_game_over(&state)
+                && playback.is_none()
+                && mq::get_last_key_pressed().is_none()
+            {
+                idle_time += delta_time;
+            } else {
+                idle_time = 0.0;
+            }
+            if idle_time > ATTRACT_DELAY {
+                attract_mode(&assets, &waves).await;
+                break;
+            }
+
+            //  online, the partner's ship is played from the other end
+            let keys = match &session {
+                Some(_) => input::read_players(1),
+                None => input::read_players(state.players.len()),
+            };
+            let restart_keys = match &session {
+                Some(s) => netplay::arrange(s, keys[0], input::Input::default()),
+                None => keys.clone(),
+            };
+            if playback.is_none() {
+                if state::wants_restart(&state, &restart_keys) {
+                    (state, turns, session) = start_game(
+                        &waves,
+                        &mut mode,
+                        &names,
+                        &ships,
+                        &mut versus,
+                        &mut online,
+                        &mut ghost,
+                    )
+                    .await;
+                    recording = replay::new(state.seed, ships[0]);
+                    recording_saved = mode != Mode::Solo;
+                } else if state::is_game_over(&state) && mq::is_key_pressed(mq::KeyCode::S) {
+                    ships = select_ships(&assets, &names, &ships).await;
+                    (state, turns, session) = start_game(
+                        &waves,
+                        &mut mode,
+                        &names,
+                        &ships,
+                        &mut versus,
+                        &mut online,
+                        &mut ghost,
+                    )
+                    .await;
+                    recording = replay::new(state.seed, ships[0]);
+                    recording_saved = mode != Mode::Solo;
+                    accumulator = 0.0;
+                } else if state::is_game_over(&state) && mode == Mode::Solo {
+                    let saved = if mq::is_key_pressed(mq::KeyCode::R) {
+                        replay::load_last()
+                    } else if mq::is_key_pressed(mq::KeyCode::B) {
+                        replay::load_best()
+                    } else {
+                        None
+                    };
+                    if mq::is_key_pressed(mq::KeyCode::G) {
+                        ghost = ghost::cycle(ghost.take(), &waves);
+                    }
+                    if let Some(r) = saved {
+                        state = state::new_game_state(r.seed, &waves, &[r.ship]);
+                        playback = Some(Playback { replay: r, tick: 0 });
+                    }
+                }
+            } else if mq::is_key_pressed(mq::KeyCode::Escape) {
+                //  stop watching; land on the game over screen
+                end_game(&mut state);
+                playback = None;
+            }
+
+            if turn_banner > 0.0 {
+                turn_banner -= delta_time;
+            } else {
+                accumulator += delta_time;
+            }
+            if let (Some(s), Some(t)) = (&mut session, &mut online) {
+                netplay::receive(s, t, &mut state, delta_time, &assets.hitboxes);
+            }
+            while accumulator >= params::TICK_TIME {
+                let inputs = match &mut playback {
+                    Some(pb) => {
+                        if pb.tick >= pb.replay.inputs.len() {
+                            break;
+                        }
+                        pb.tick += 1;
+                        vec![pb.replay.inputs[pb.tick - 1]]
+                    }
+                    None => {
+                        if !recording_saved {
+                            replay::record(&mut recording, &keys[0]);
+                        }
+                        keys.clone()
+                    }
+                };
+                match &mut session {
+                    Some(s) => {
+                        if !netplay::tick(s, &mut state, inputs[0], &assets.hitboxes) {
+                            //  too far ahead of the partner; hold on for them
+                            accumulator = 0.0;
+                            break;
+                        }
+                    }
+                    None => state::update_state(
+                        params::TICK_TIME,
+                        &mut state,
+                        &inputs,
+                        &assets.hitboxes,
+                    ),
+                }
+                //  the ghost keeps pace with the live game, until that's over
+                if let Some(g) = &mut ghost {
+                    if playback.is_none() && !state.paused && !state::is_game_over(&state) {
+                        ghost::step(g, &assets.hitboxes);
+                    }
+                }
+                accumulator -= params::TICK_TIME;
+                if state.turn_over {
+                    break;
+                }
+            }
+            if state.turn_over {
+                state.turn_over = false;
+                if let Some(t) = &mut turns {
+                    if turns::end_turn(t, &mut state) {
+                        turn_banner = TURN_BANNER_TIME;
+                        accumulator = 0.0;
+                    }
+                }
+            }
+            if let Some(v) = &mut versus {
+                versus::exchange(v, &mut state, delta_time);
+            }
+            if let (Some(s), Some(t)) = (&mut session, &mut online) {
+                netplay::send(s, t);
+            }
+            if let Some(pb) = &playback {
+                if pb.tick >= pb.replay.inputs.len() {
+                    end_game(&mut state);
+                    playback = None;
+                    accumulator = 0.0;
+                }
+            } else if state::is_game_over(&state) && !recording_saved {
+                recording.score = state.score;
+                replay::save(&recording);
+                recording_saved = true;
+            }
+
+            sounds::update_sounds(delta_time, &mut state, &assets);
+            let racing = ghost.as_ref().filter(|_| playback.is_none());
+            render_scene(
+                &state,
+                &assets,
+                accumulator / params::TICK_TIME,
+                mode,
+                racing,
+            );
+            draw_scores(&state, &turns);
+            if let Some(g) = racing {
+                draw_ghost_score(g);
+            }
+            if let Some(v) = &versus {
+                draw_opponent(v);
+            }
+            if let Some(s) = &session {
+                draw_partner(s);
+            }
+            if playback.is_some() {
+                draw_replay_banner();
+            }
+            if turn_banner > 0.0 {
+                draw_turn_banner(&state.players[0].name);
+            }
+
+            mq::next_frame().await;
+        }
+    }
+}
+
+struct Playback {
+    replay: replay::Replay,
+    tick: usize,
+}
+
+//  How the players share the game
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Solo,
+    Turns,  //  one ship at a time, swapping when one is lost
+    CoOp,   //  both ships on screen at once
+    Versus, //  against someone else's game, through the relay
+    Online, //  together, with the partner on another machine
+}
+
+//  Straight to the game over screen, as when a replay is stopped
+fn end_game(state: &mut state::State) {
+    for p in state.players.iter_mut() {
+        p.state = state::PlayerState::GameOver;
+    }
+}
+
+const TURN_BANNER_TIME: f32 = 1.5;
+//  Attract mode: how long the game over screen waits, and how long each
+//  page shows; the demo game runs until it's lost or its time is up
+const ATTRACT_DELAY: f32 = 20.0;
+const ATTRACT_PAGE_TIME: f32 = 8.0;
+const DEMO_TIME: f32 = 45.0;
+const GHOST_TINT: mq::Color = mq::Color::new(0.6, 0.8, 1.0, 0.35);
+//  How long a browser gets to open its socket to the relay
+const RELAY_TIMEOUT: f32 = 5.0;
+const MAX_NAME_LEN: usize = 10;
+
+//  Taking turns, that's player 1's game plus player 2's waiting its turn.
+//  Both get the same seed, so they face the same game.
+fn new_game(
+    waves: &Rc<Vec<wave::Wave>>,
+    mode: Mode,
+    names: &[String],
+    ships: &[ship::Ship],
+    seed: u64,
+) -> (state::State, Option<turns::Turns>) {
+    if mode == Mode::CoOp {
+        let mut state = state::new_game_state(seed, waves, ships);
+        for (p, name) in state.players.iter_mut().zip(names.iter()) {
+            p.name = name.clone();
+        }
+        return (state, None);
+    }
+    let mut state = state::new_game_state(seed, waves, &ships[..1]);
+    state.players[0].name = names[0].clone();
+    if mode == Mode::Versus {
+        state.outbox = Some(Vec::new());
+    }
+    let mut turns = None;
+    if mode == Mode::Turns {
+        let mut waiting = state::new_game_state(seed, waves, &ships[1..2]);
+        waiting.players[0].name = names[1].clone();
+        turns = Some(turns::new(waiting));
+    }
+    return (state, turns);
+}
+
+//  Network games play the seed the relay hands out, which means waiting
+//  for someone to play with first; giving up on that drops back to playing
+//  alone. Online, the partner's ship and name come from their end.
+async fn start_game(
+    waves: &Rc<Vec<wave::Wave>>,
+    mode: &mut Mode,
+    names: &[String],
+    ships: &[ship::Ship],
+    versus: &mut Option<versus::Versus>,
+    online: &mut Option<netplay::Transport>,
+    ghost: &mut Option<ghost::Ghost>,
+) -> (state::State, Option<turns::Turns>, Option<netplay::Session>) {
+    if let Some(t) = online {
+        if let Some((seed, seat, ship, name)) = wait_for_partner(t, ships[0], &names[0]).await {
+            let mut both_ships = vec![ship; 2];
+            both_ships[seat] = ships[0];
+            let mut both_names = vec![name; 2];
+            both_names[seat] = names[0].clone();
+            let (mut state, _) = new_game(waves, Mode::CoOp, &both_names, &both_ships, seed);
+            let session = netplay::new(seat, &mut state);
+            return (state, None, Some(session));
+        }
+        *online = None;
+        *mode = Mode::Solo;
+    }
+    //  racing a ghost means playing its game
+    let seed = match ghost {
+        Some(g) if *mode == Mode::Solo => {
+            ghost::restart(g, waves);
+            g.replay.seed
+        }
+        _ => next_seed(mode, versus).await,
+    };
+    let (state, turns) = new_game(waves, *mode, names, ships, seed);
+    return (state, turns, None);
+}
+
+async fn next_seed(mode: &mut Mode, versus: &mut Option<versus::Versus>) -> u64 {
+    if let Some(v) = versus {
+        versus::find_match(v);
+        if let Some(seed) = wait_for_opponent(v).await {
+            return seed;
+        }
+        *versus = None;
+        *mode = Mode::Solo;
+    }
+    return new_seed();
+}
+
+//  Natively the socket is open once connect() returns; a browser's opens in
+//  the background, and nothing may be sent before it has
+async fn wait_for_relay(
+    versus: &Option<versus::Versus>,
+    online: &Option<netplay::Transport>,
+) -> Result<(), String> {
+    let mut waited = 0.0;
+    loop {
+        let open = match (versus, online) {
+            (Some(v), _) => versus::is_connected(v),
+            (_, Some(t)) => netplay::is_connected(t),
+            _ => true,
+        };
+        if open {
+            return Ok(());
+        }
+        if waited > RELAY_TIMEOUT {
+            return Err(format!("can't reach the relay at {}", versus::RELAY_WS_URL));
+        }
+        draw_waiting("Connecting...");
+        waited += mq::get_frame_time();
+        mq::next_frame().await;
+    }
+}
+
+async fn wait_for_opponent(v: &mut versus::Versus) -> Option<u64> {
+    loop {
+        while let Some(msg) = versus::receive(v) {
+            if let versus::Message::Start(seed, _) = msg {
+                mq::next_frame().await;
+                return Some(seed);
+            }
+        }
+        if mq::is_key_pressed(mq::KeyCode::Escape) {
+            mq::next_frame().await;
+            return None;
+        }
+        draw_waiting("Waiting for an opponent...");
+        mq::next_frame().await;
+    }
+}
+
+//  The seed, which player this side is, and the partner's ship and name
+async fn wait_for_partner(
+    t: &mut netplay::Transport,
+    ship: ship::Ship,
+    name: &str,
+) -> Option<(u64, usize, ship::Ship, String)> {
+    netplay::find_partner(t);
+    let mut start: Option<(u64, usize)> = None;
+    loop {
+        if start.is_none() {
+            start = netplay::receive_start(t);
+            if let Some((_, seat)) = start {
+                //  there are only two players; anything else is a broken relay
+                if seat > 1 {
+                    show_message(&format!("The relay gave this side seat {}", seat)).await;
+                    return None;
+                }
+                netplay::send_join(t, ship, name);
+            }
+        }
+        if let Some((seed, seat)) = start {
+            if let Some((ship, name)) = netplay::receive_join(t) {
+                mq::next_frame().await;
+                return Some((seed, seat, ship, name));
+            }
+        }
+        if mq::is_key_pressed(mq::KeyCode::Escape) {
+            mq::next_frame().await;
+            return None;
+        }
+        draw_waiting("Waiting for a partner...");
+        mq::next_frame().await;
+    }
+}
+
+fn draw_waiting(text: &str) {
+    mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    mq::draw_text(
+        text,
+        left + width * 0.25,
+        top + height * 0.45,
+        height * 0.04,
+        mq::WHITE,
+    );
+    mq::draw_text(
+        "ESC to play alone",
+        left + width * 0.38,
+        top + height * 0.55,
+        height * 0.025,
+        mq::LIGHTGRAY,
+    );
+}
+
+async fn show_message(text: &str) {
+    let mut accumtime = 0.0;
+    while accumtime < 2.0 {
+        mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+        let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+        mq::draw_text(
+            text,
+            left + width * 0.05,
+            top + height * 0.5,
+            height * 0.025,
+            mq::WHITE,
+        );
+        mq::next_frame().await;
+        accumtime += mq::get_frame_time().min(0.1);
+    }
+}
+
+enum AttractPage {
+    HighScores,
+    Legend,
+    Demo(state::State),
+}
 
+//  Round the pages until a key is pressed, then back to the title
+async fn attract_mode(assets: &assets::Assets, waves: &Rc<Vec<wave::Wave>>) {
+    let scores = highscore::get_list();
+    let mut page = AttractPage::HighScores;
+    let mut page_time = 0.0;
+    let mut accumulator = 0.0;
     loop {
+        if mq::get_last_key_pressed().is_some() {
+            mq::next_frame().await;
+            return;
+        }
         let delta_time = mq::get_frame_time().min(0.1);
+        page_time += delta_time;
+
+        let page_over = match &mut page {
+            AttractPage::HighScores => {
+                draw_high_scores(&scores);
+                page_time > ATTRACT_PAGE_TIME
+            }
+            AttractPage::Legend => {
+                draw_legend(assets);
+                page_time > ATTRACT_PAGE_TIME
+            }
+            AttractPage::Demo(demo) => {
+                accumulator += delta_time;
+                while accumulator >= params::TICK_TIME {
+                    let input = demo::play(demo);
+                    state::update_state(params::TICK_TIME, demo, &[input], &assets.hitboxes);
+                    accumulator -= params::TICK_TIME;
+                }
+                let alpha = accumulator / params::TICK_TIME;
+                render_scene(demo, assets, alpha, Mode::Solo, None);
+                draw_scores(demo, &None);
+                state::is_game_over(demo) || page_time > DEMO_TIME
+            }
+        };
+        draw_attract_footer(&page);
+
+        if page_over {
+            page = match page {
+                AttractPage::HighScores => AttractPage::Legend,
+                AttractPage::Legend => {
+                    //  the demo player's scores aren't anyone's
+                    let ships = [ship::DEFAULT_SHIP];
+                    let mut demo = state::new_game_state(new_seed(), waves, &ships);
+                    demo.quiet = true;
+                    accumulator = 0.0;
+                    AttractPage::Demo(demo)
+                }
+                AttractPage::Demo(_) => AttractPage::HighScores,
+            };
+            page_time = 0.0;
+        }
+        mq::next_frame().await;
+    }
+}
+
+fn draw_high_scores(scores: &[highscore::Score]) {
+    mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    mq::draw_text(
+        "High Scores",
+        left + width * 0.33,
+        top + height * 0.2,
+        height * 0.06,
+        mq::WHITE,
+    );
+    let listed: Vec<&highscore::Score> = scores.iter().filter(|s| s.score > 0).collect();
+    if listed.is_empty() {
+        mq::draw_text(
+            "No scores yet",
+            left + width * 0.36,
+            top + height * 0.4,
+            height * 0.035,
+            mq::GRAY,
+        );
+    }
+    for (row, s) in listed.iter().enumerate() {
+        let ypos = top + height * (0.32 + 0.05 * row as f32);
+        let name = if s.name.is_empty() { "---" } else { &s.name };
+        let color = if row == 0 { mq::YELLOW } else { mq::WHITE };
+        mq::draw_text(
+            &format!("{}.", row + 1),
+            left + width * 0.25,
+            ypos,
+            height * 0.035,
+            color,
+        );
+        mq::draw_text(name, left + width * 0.32, ypos, height * 0.035, color);
+        mq::draw_text(
+            &format!("{:>6}", s.score),
+            left + width * 0.62,
+            ypos,
+            height * 0.035,
+            color,
+        );
+    }
+}
+
+//  What each kind of alien is worth, as in the classic formation, top row
+//  first; the UFO's worth is a mystery, as it was on the arcade
+fn draw_legend(assets: &assets::Assets) {
+    mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    mq::draw_text(
+        "Score Advance Table",
+        left + width * 0.24,
+        top + height * 0.2,
+        height * 0.05,
+        mq::WHITE,
+    );
+    let mut rows: Vec<(&sprite::Sprite, String)> =
+        vec![(&assets::UFO_SPRITES[0], "= ? MYSTERY".to_string())];
+    for row in wave::classic().rows.iter() {
+        if let Some(spr) = wave::sprite_by_name(&row.sprite) {
+            if !rows.iter().any(|(s, _)| std::ptr::eq(*s, spr)) {
+                rows.push((spr, format!("= {} POINTS", row.points)));
+            }
+        }
+    }
+    for (ix, (spr, text)) in rows.iter().enumerate() {
+        let yfr = 0.35 + 0.1 * ix as f32;
+        let lspr = &assets.sprites[spr.index];
+        //  sprites are placed in widths, text in heights
+        sprite::draw_sprite(left, top, width, lspr, 0.35, yfr * height / width, 0.0, 1.0);
+        mq::draw_text(
+            text,
+            left + width * 0.45,
+            top + height * (yfr + 0.012),
+            height * 0.035,
+            mq::WHITE,
+        );
+    }
+}
+
+fn draw_attract_footer(page: &AttractPage) {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    if let AttractPage::Demo(_) = page {
+        mq::draw_text(
+            "DEMO",
+            left + width * 0.46,
+            top + height * 0.12,
+            height * 0.04,
+            mq::YELLOW,
+        );
+    }
+    mq::draw_text(
+        "Press any key",
+        left + width * 0.38,
+        top + height * 0.9,
+        height * 0.03,
+        mq::LIGHTGRAY,
+    );
+}
+
+//  Pick a mode, then everyone types a name; returns one name per player
+async fn intro_screen() -> (Mode, Vec<String>) {
+    let mut mode: Option<Mode> = None;
+    while mode.is_none() {
+        if mq::is_key_pressed(mq::KeyCode::Key1) || mq::is_key_pressed(mq::KeyCode::Space) {
+            mode = Some(Mode::Solo);
+        } else if mq::is_key_pressed(mq::KeyCode::Key2) {
+            mode = Some(Mode::Turns);
+        } else if mq::is_key_pressed(mq::KeyCode::Key3) {
+            mode = Some(Mode::CoOp);
+        } else if mq::is_key_pressed(mq::KeyCode::Key4) {
+            mode = Some(Mode::Versus);
+        } else if mq::is_key_pressed(mq::KeyCode::Key5) {
+            mode = Some(Mode::Online);
+        }
+
+        mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+        let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+        mq::draw_text(
+            "Space Observers",
+            left + width * 0.27,
+            top + height * 0.3,
+            height * 0.07,
+            mq::WHITE,
+        );
+        let choices = [
+            "1: one player",
+            "2: two players, taking turns",
+            "3: two players together",
+            "4: versus over the network",
+            "5: two players together over the network",
+        ];
+        for (row, text) in choices.iter().enumerate() {
+            mq::draw_text(
+                text,
+                left + width * 0.3,
+                top + height * (0.5 + 0.05 * row as f32),
+                height * 0.03,
+                mq::WHITE,
+            );
+        }
+        mq::draw_text(
+            "Together: arrows and SPACE, or A/D and W; or a gamepad each",
+            left + width * 0.1,
+            top + height * 0.7,
+            height * 0.022,
+            mq::LIGHTGRAY,
+        );
+        mq::next_frame().await;
+    }
+    let mode = mode.unwrap_or(Mode::Solo);
+    let players = match mode {
+        Mode::Solo | Mode::Versus | Mode::Online => 1,
+        Mode::Turns | Mode::CoOp => 2,
+    };
+    let mut names: Vec<String> = Vec::new();
+    for ix in 0..players {
+        names.push(enter_name(ix, players).await);
+    }
+    return (mode, names);
+}
+
+//  With two players, a blank name becomes "PLAYER n"; a single player's
+//  blank name stays blank, and the high score goes under their login
+async fn enter_name(ix: usize, players: usize) -> String {
+    //  whatever was typed on the screen before isn't part of the name
+    while mq::get_char_pressed().is_some() {}
+    let mut name = String::new();
+    loop {
+        while let Some(c) = mq::get_char_pressed() {
+            if (c.is_ascii_alphanumeric() || c == ' ') && name.len() < MAX_NAME_LEN {
+                name.push(c.to_ascii_uppercase());
+            }
+        }
+        if mq::is_key_pressed(mq::KeyCode::Backspace) {
+            name.pop();
+        }
+        let done = mq::is_key_pressed(mq::KeyCode::Enter);
 
-        state::update_state(delta_time, &mut state, &assets);
-        sounds::update_sounds(delta_time, &mut state, &assets);
-        render_scene(&state, &assets);
+        mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+        let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+        let prompt = if players > 1 {
+            format!("Player {}, enter your name", ix + 1)
+        } else {
+            "Enter your name".to_string()
+        };
+        mq::draw_text(
+            &prompt,
+            left + width * 0.25,
+            top + height * 0.35,
+            height * 0.04,
+            mq::WHITE,
+        );
+        //  blinking cursor
+        let cursor = if (mq::get_time() * 2.0) as usize % 2 == 0 {
+            "_"
+        } else {
+            ""
+        };
+        mq::draw_text(
+            &format!("{}{}", name, cursor),
+            left + width * 0.3,
+            top + height * 0.47,
+            height * 0.05,
+            mq::YELLOW,
+        );
+        mq::draw_text(
+            "ENTER: done    BACKSPACE: delete",
+            left + width * 0.27,
+            top + height * 0.6,
+            height * 0.025,
+            mq::WHITE,
+        );
+        //  let the key go before the next screen looks at it
+        mq::next_frame().await;
+        if done {
+            let name = name.trim().to_string();
+            if name.is_empty() && players > 1 {
+                return format!("PLAYER {}", ix + 1);
+            }
+            return name;
+        }
+    }
+}
+
+//  Everyone picks a ship in turn, starting from what they flew last time
+async fn select_ships(
+    assets: &assets::Assets,
+    names: &[String],
+    current: &[ship::Ship],
+) -> Vec<ship::Ship> {
+    let mut ret: Vec<ship::Ship> = Vec::new();
+    for (ix, name) in names.iter().enumerate() {
+        let title = if names.len() > 1 {
+            format!("{}, choose your ship", name)
+        } else {
+            "Choose your ship".to_string()
+        };
+        let start = current.get(ix).copied().unwrap_or(ship::DEFAULT_SHIP);
+        let ship = select_ship(assets, start, &title).await;
+        ret.push(ship);
+    }
+    return ret;
+}
+
+//  Left/Right picks the hull, Up/Down the paint; Space to fly
+async fn select_ship(assets: &assets::Assets, current: ship::Ship, title: &str) -> ship::Ship {
+    let mut choice = current;
+    let ncolours = ship::COLOURS.len();
+    loop {
+        if mq::is_key_pressed(mq::KeyCode::Left) && choice.hull > 0 {
+            choice.hull -= 1;
+        }
+        if mq::is_key_pressed(mq::KeyCode::Right) && choice.hull + 1 < ship::HULLS.len() {
+            choice.hull += 1;
+        }
+        if mq::is_key_pressed(mq::KeyCode::Up) {
+            choice.colour = (choice.colour + ncolours - 1) % ncolours;
+        }
+        if mq::is_key_pressed(mq::KeyCode::Down) {
+            choice.colour = (choice.colour + 1) % ncolours;
+        }
+        let done = mq::is_key_pressed(mq::KeyCode::Space) || mq::is_key_pressed(mq::KeyCode::Enter);
 
+        mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
+        let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+        mq::draw_text(
+            title,
+            left + width * 0.3,
+            top + height * 0.15,
+            height * 0.05,
+            mq::WHITE,
+        );
+        //  stats are drawn relative to the best hull at each
+        let max_speed = ship::HULLS
+            .iter()
+            .fold(0.0, |m: f32, h| m.max(h.player_speed));
+        let min_cooldown = ship::HULLS
+            .iter()
+            .fold(10.0, |m: f32, h| m.min(h.firing_duration));
+        let max_velocity = ship::HULLS
+            .iter()
+            .fold(0.0, |m: f32, h| m.max(h.fire_velocity));
+        for (ix, hull) in ship::HULLS.iter().enumerate() {
+            let xpos = 0.2 + 0.3 * ix as f32;
+            let shown = ship::Ship {
+                hull: ix,
+                colour: choice.colour,
+            };
+            if ix == choice.hull {
+                mq::draw_rectangle_lines(
+                    left + width * (xpos - 0.13),
+                    top + height * 0.25,
+                    width * 0.26,
+                    height * 0.5,
+                    3.0,
+                    mq::YELLOW,
+                );
+            }
+            let sspr = &assets.sprites[ship::sprite(shown).index];
+            sprite::draw_sprite(left, top, width, sspr, xpos, 0.45, 0.0, 1.5);
+            mq::draw_text(
+                hull.name,
+                left + width * (xpos - 0.08),
+                top + height * 0.5,
+                height * 0.03,
+                mq::WHITE,
+            );
+            let stats = [
+                ("speed", hull.player_speed / max_speed),
+                ("fire rate", min_cooldown / hull.firing_duration),
+                ("shot speed", hull.fire_velocity / max_velocity),
+            ];
+            for (row, (label, frac)) in stats.iter().enumerate() {
+                let ypos = 0.56 + 0.05 * row as f32;
+                mq::draw_text(
+                    label,
+                    left + width * (xpos - 0.11),
+                    top + height * ypos,
+                    height * 0.022,
+                    mq::LIGHTGRAY,
+                );
+                mq::draw_rectangle(
+                    left + width * (xpos - 0.11),
+                    top + height * (ypos + 0.008),
+                    width * 0.22 * frac,
+                    height * 0.012,
+                    mq::GREEN,
+                );
+            }
+        }
+        mq::draw_text(
+            "LEFT/RIGHT: hull    UP/DOWN: colour    SPACE: start",
+            left + width * 0.17,
+            top + height * 0.85,
+            height * 0.025,
+            mq::WHITE,
+        );
+        //  let the key go before the next screen looks at it
         mq::next_frame().await;
+        if done {
+            return choice;
+        }
     }
 }
 
+fn new_seed() -> u64 {
+    return mq::miniquad::date::now().to_bits();
+}
+
+//  One score, or both players'; whoever is up (or still in) is in yellow
+fn draw_scores(state: &state::State, turns: &Option<turns::Turns>) {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    let shown: Vec<(&str, i32, bool)> = match turns {
+        Some(t) => (0..2)
+            .map(|ix| {
+                let game = turns::player(t, state, ix);
+                (game.players[0].name.as_str(), game.score, ix == t.current)
+            })
+            .collect(),
+        None if state.players.len() > 1 => state
+            .players
+            .iter()
+            .map(|p| {
+                let playing = p.state != state::PlayerState::GameOver;
+                (p.name.as_str(), p.score, playing)
+            })
+            .collect(),
+        None => {
+            mq::draw_text(
+                &format!("Score: {}", state.score),
+                left + width * 0.01,
+                top + height * 0.03,
+                height * 0.04,
+                mq::WHITE,
+            );
+            return;
+        }
+    };
+    for (ix, (name, score, up)) in shown.into_iter().enumerate() {
+        let color = if up { mq::YELLOW } else { mq::GRAY };
+        let xpos = if ix == 0 { 0.01 } else { 0.78 };
+        mq::draw_text(
+            name,
+            left + width * xpos,
+            top + height * 0.022,
+            height * 0.025,
+            color,
+        );
+        mq::draw_text(
+            &format!("{}", score),
+            left + width * xpos,
+            top + height * 0.05,
+            height * 0.035,
+            color,
+        );
+    }
+}
+
+//  The other side's game, in the corner where a second player's score goes
+fn draw_opponent(v: &versus::Versus) {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    let (status, color) = match v.outcome {
+        versus::Outcome::Playing => (format!("Lives: {}", v.opponent_lives), mq::GRAY),
+        versus::Outcome::Won => ("You win!".to_string(), mq::GREEN),
+        versus::Outcome::Lost => ("You lose".to_string(), mq::RED),
+        versus::Outcome::OpponentLeft => ("Opponent left".to_string(), mq::YELLOW),
+    };
+    mq::draw_text(
+        "Opponent",
+        left + width * 0.78,
+        top + height * 0.022,
+        height * 0.025,
+        mq::GRAY,
+    );
+    mq::draw_text(
+        &format!("{}", v.opponent_score),
+        left + width * 0.78,
+        top + height * 0.05,
+        height * 0.035,
+        mq::GRAY,
+    );
+    mq::draw_text(
+        &status,
+        left + width * 0.78,
+        top + height * 0.075,
+        height * 0.025,
+        color,
+    );
+}
+
+//  Under the live score, in the ghost's colour
+fn draw_ghost_score(g: &ghost::Ghost) {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    mq::draw_text(
+        &format!("{}: {}", ghost::label(g.source), g.state.score),
+        left + width * 0.01,
+        top + height * 0.065,
+        height * 0.03,
+        mq::Color::new(0.6, 0.8, 1.0, 0.8),
+    );
+}
+
+//  Only when something's wrong with the link
+fn draw_partner(s: &netplay::Session) {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    let (text, color) = if s.desync_tick.is_some() {
+        ("Out of sync!", mq::RED)
+    } else if s.partner_left {
+        ("Partner left", mq::YELLOW)
+    } else {
+        return;
+    };
+    mq::draw_text(
+        text,
+        left + width * 0.42,
+        top + height * 0.03,
+        height * 0.03,
+        color,
+    );
+}
+
+fn draw_turn_banner(name: &str) {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    mq::draw_rectangle(
+        left + width * 0.2,
+        top + height * 0.4,
+        width * 0.6,
+        height * 0.12,
+        mq::BLACK,
+    );
+    mq::draw_text(
+        name,
+        left + width * 0.35,
+        top + height * 0.45,
+        height * 0.04,
+        mq::YELLOW,
+    );
+    mq::draw_text(
+        "Get ready!",
+        left + width * 0.38,
+        top + height * 0.5,
+        height * 0.035,
+        mq::WHITE,
+    );
+}
+
+fn draw_replay_banner() {
+    let (left, top, width, height) = letterbox(mq::screen_width(), mq::screen_height());
+    mq::draw_text(
+        "REPLAY - ESC to stop",
+        left + width * 0.6,
+        top + height * 0.03,
+        height * 0.03,
+        mq::YELLOW,
+    );
+}
+
 async fn draw_intro() {
     let mut accumtime = 0.0;
     while accumtime < 1.0 {
@@ -57,24 +1103,46 @@ async fn draw_intro() {
     }
 }
 
-fn render_scene(state: &state::State, assets: &assets::Assets) {
+fn lerp(from: f32, to: f32, alpha: f32) -> f32 {
+    return from + (to - from) * alpha;
+}
+
+//  alpha is how far we are between the previous tick and the current one
+fn render_scene(
+    state: &state::State,
+    assets: &assets::Assets,
+    alpha: f32,
+    mode: Mode,
+    ghost: Option<&ghost::Ghost>,
+) {
     mq::clear_background(mq::Color::new(0.11, 0.11, 0.11, 1.00));
 
     let sw = mq::screen_width();
     let sh = mq::screen_height();
     let (left, top, width, height) = letterbox(sw, sh);
 
-    if state.player_state != state::PlayerState::GameOver {
-        {
-            //  draw lives
-            let pspr = &assets.sprites[assets::IX_PLAYER];
-            for n in 0..state.lives {
+    if !state::is_game_over(state) {
+        //  the ghost goes under the live ship
+        if let Some(g) = ghost {
+            let p = &g.state.players[0];
+            if p.state == state::PlayerState::Playing {
+                let xpos = lerp(p.last_pos_fr, p.pos_fr, alpha);
+                draw_player(left, top, width, p, assets, xpos, 1.0, GHOST_TINT);
+            }
+        }
+
+        for (ix, p) in state.players.iter().enumerate() {
+            let player_pos_fr = lerp(p.last_pos_fr, p.pos_fr, alpha);
+            //  draw lives; player 2's line up from the right
+            let pspr = &assets.sprites[ship::sprite(p.ship).index];
+            for n in 0..p.lives {
+                let xpos = (n as f32 + 0.6) * pspr.f_w * 0.5;
                 sprite::draw_sprite(
                     left,
                     top,
                     width,
                     pspr,
-                    (n as f32 + 0.6) * pspr.f_w * 0.5,
+                    if ix == 0 { xpos } else { 1.0 - xpos },
                     1.333 - pspr.f_h * 0.6 * 0.45,
                     0.0,
                     0.45,
@@ -82,57 +1150,117 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
             }
 
             //  draw player
-            if state.player_state == state::PlayerState::Playing {
-                sprite::draw_sprite(
-                    left,
-                    top,
-                    width,
-                    pspr,
-                    state.player_pos_fr,
-                    1.333 - 0.06,
-                    0.0,
-                    1.0,
-                );
-            } else if state.player_state == state::PlayerState::HitRespawning {
-                //  animate in from the left
-                let alpha = state.player_hit_timer / state::HIT_RESPAWN_TIME;
-                let scale = if ((alpha * 10.0) as usize % 2) == 1 {
+            if p.state == state::PlayerState::Playing {
+                draw_player(left, top, width, p, assets, player_pos_fr, 1.0, mq::WHITE);
+                if p.shield_time > 0.0 {
+                    draw_shield(left, top, width, p, assets, player_pos_fr);
+                }
+            } else if p.state == state::PlayerState::HitRespawning {
+                //  animate in from the player's own side
+                let anim = p.hit_timer / state::HIT_RESPAWN_TIME;
+                let scale = if ((anim * 10.0) as usize % 2) == 1 {
                     1.0
                 } else {
                     0.0
                 };
-                sprite::draw_sprite(
-                    left,
-                    top,
-                    width,
-                    pspr,
-                    state.player_pos_fr - alpha * state.player_pos_fr,
-                    1.333 - 0.06,
-                    0.0,
-                    scale,
+                let xpos = if ix == 0 {
+                    player_pos_fr - anim * player_pos_fr
+                } else {
+                    player_pos_fr + anim * (1.0 - player_pos_fr)
+                };
+                draw_player(left, top, width, p, assets, xpos, scale, mq::WHITE);
+            }
+        }
+
+        if let Some(u) = &state.ufo {
+            let xpos = lerp(u.last_xpos, u.xpos, alpha);
+            if ufo::is_shown_as_points(u) {
+                mq::draw_text(
+                    &format!("{}", u.points),
+                    left + width * (xpos - 0.03),
+                    top + width * ufo::UFO_Y,
+                    height * 0.035,
+                    mq::YELLOW,
                 );
+            } else {
+                let usp = &assets.sprites[u.sprite.index];
+                sprite::draw_sprite(left, top, width, usp, xpos, ufo::UFO_Y, u.phase, 1.0);
             }
         }
 
+        for bk in state.bunkers.iter() {
+            bunker::render(left, top, width, bk);
+        }
+
+        if let Some(b) = &state.boss {
+            draw_boss(left, top, width, height, b, assets, alpha);
+        }
+
         //  draw aliens
         for alien in state.aliens.iter() {
             let asp = &assets.sprites[alien.sprite.index];
-            sprite::draw_sprite(
+            //  armored aliens flash red when a hit doesn't finish them
+            let tint = if alien.flash_timer > 0.0 {
+                mq::Color::new(1.0, 0.3, 0.3, 1.0)
+            } else {
+                mq::WHITE
+            };
+            //  divers turn to face where they're heading
+            let rotation = if alien.dive.is_some() {
+                (alien.ypos - alien.last_ypos).atan2(alien.xpos - alien.last_xpos)
+                    - std::f32::consts::FRAC_PI_2
+            } else {
+                alien.phase.sin() * 0.1
+            };
+            sprite::draw_sprite_tinted(
                 left,
                 top,
                 width,
                 asp,
-                alien.xpos,
-                alien.ypos,
-                alien.phase.sin() * 0.1,
+                lerp(alien.last_xpos, alien.xpos, alpha),
+                lerp(alien.last_ypos, alien.ypos, alpha),
+                rotation,
                 1.0,
+                tint,
             );
         }
-        {
-            let bspr = &assets.sprites[assets::IX_BOMB];
-            for bomb in state.bombs.iter() {
-                sprite::draw_sprite(left, top, width, bspr, bomb.xpos, bomb.ypos, 0.0, 1.0);
-            }
+        for bomb in state.bombs.iter() {
+            let bspr = &assets.sprites[bomb.sprite.index];
+            let xpos = lerp(bomb.last_xpos, bomb.xpos, alpha);
+            let ypos = lerp(bomb.last_ypos, bomb.ypos, alpha);
+            let tilt = if bomb.kind.zigzag > 0.0 {
+                bomb.phase.cos() * 0.4
+            } else {
+                0.0
+            };
+            sprite::draw_sprite(left, top, width, bspr, xpos, ypos, tilt, 1.0);
+        }
+        for m in state.meteors.iter() {
+            let mspr = &assets.sprites[m.sprite.index];
+            sprite::draw_sprite(
+                left,
+                top,
+                width,
+                mspr,
+                lerp(m.last_xpos, m.xpos, alpha),
+                lerp(m.last_ypos, m.ypos, alpha),
+                lerp(m.last_rotation, m.rotation, alpha),
+                1.0,
+            );
+        }
+
+        for p in state.powerups.iter() {
+            let pspr = &assets.sprites[p.kind.sprite.index];
+            sprite::draw_sprite(
+                left,
+                top,
+                width,
+                pspr,
+                p.xpos,
+                lerp(p.last_ypos, p.ypos, alpha),
+                p.phase.sin() * 0.2,
+                1.0,
+            );
         }
 
         {
@@ -144,8 +1272,8 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
                     top,
                     width,
                     bspr,
-                    bullet.xpos,
-                    bullet.ypos + bspr.f_h * 0.5,
+                    lerp(bullet.last_xpos, bullet.xpos, alpha),
+                    lerp(bullet.last_ypos, bullet.ypos, alpha) + bspr.f_h * 0.5,
                     0.0,
                     1.0,
                 );
@@ -170,6 +1298,18 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
             height * 0.04,
             mq::WHITE,
         );
+        let why = match state::game_over_reason(state) {
+            state::GameOverReason::OutOfLives => "Your last ship was destroyed",
+            state::GameOverReason::Overrun => "The invaders overran your ship",
+            state::GameOverReason::Landed => "The invaders have landed",
+        };
+        mq::draw_text(
+            why,
+            left + width * 0.3,
+            top + height * 0.4,
+            height * 0.03,
+            mq::RED,
+        );
         mq::draw_text(
             "Press SPACE to play again",
             left + width * 0.32,
@@ -177,6 +1317,33 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
             height * 0.025,
             mq::WHITE,
         );
+        let hint = match mode {
+            Mode::Solo => "R: watch last run    B: watch best run    S: change ship",
+            Mode::Versus | Mode::Online => "S: change ship",
+            Mode::Turns | Mode::CoOp => "S: change ships",
+        };
+        let hint_x = if mode == Mode::Solo { 0.2 } else { 0.41 };
+        mq::draw_text(
+            hint,
+            left + width * hint_x,
+            top + height * 0.56,
+            height * 0.025,
+            mq::WHITE,
+        );
+        if mode == Mode::Solo {
+            let against = match ghost.map(|g| g.source) {
+                None => "nobody",
+                Some(ghost::Source::Best) => "your best run",
+                Some(ghost::Source::Top) => "the top run",
+            };
+            mq::draw_text(
+                &format!("G: race against {}", against),
+                left + width * 0.35,
+                top + height * 0.62,
+                height * 0.025,
+                mq::WHITE,
+            );
+        }
     }
 
     //  draw masking bars
@@ -185,15 +1352,11 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
     mq::draw_rectangle(left + width, 0.0, sw - (left + width), sh, mq::BLACK);
     mq::draw_rectangle(left, top + height, width, sh - (top + height), mq::BLACK);
 
-    //  draw score
-    let scoredisp = format!("Score: {}", state.score);
-    mq::draw_text(
-        &scoredisp,
-        left + width * 0.01,
-        top + height * 0.03,
-        height * 0.04,
-        mq::WHITE,
-    );
+    //  player 2's power-ups go the other side of the middle
+    for (ix, p) in state.players.iter().enumerate() {
+        let xpos = if ix == 0 { 0.3 } else { 0.55 };
+        draw_active_powerups(left, top, width, height, p, xpos, assets);
+    }
 
     if state.paused {
         let pdisp = format!("Press ESC to Unpause");
@@ -211,6 +1374,14 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
             height * 0.04,
             mq::WHITE,
         );
+    } else if state.meteor_shower && state.meteors_to_come > meteor::SHOWER_METEORS - 3 {
+        mq::draw_text(
+            "Meteor shower!",
+            left + width * 0.33,
+            top + height * 0.2,
+            height * 0.04,
+            mq::ORANGE,
+        );
     } else if state.reset_countdown > 0.0 {
         let ctdisp = format!("Countdown: {:.1}", state.reset_countdown);
         mq::draw_text(
@@ -223,6 +1394,145 @@ fn render_scene(state: &state::State, assets: &assets::Assets) {
     }
 }
 
+const FIRE_FPS: f64 = 20.0;
+
+//  Engine fire underneath, then the hull, then any battle damage on top
+fn draw_player(
+    left: f32,
+    top: f32,
+    width: f32,
+    p: &state::Player,
+    assets: &assets::Assets,
+    xpos: f32,
+    scale: f32,
+    tint: mq::Color,
+) {
+    let pspr = &assets.sprites[ship::sprite(p.ship).index];
+    let frame = (mq::get_time() * FIRE_FPS) as usize % assets::FIRE_SPRITES.len();
+    let fspr = &assets.sprites[assets::FIRE_SPRITES[frame].index];
+    //  draw_sprite puts a sprite's top edge at y - f_w / 2; tuck the top of
+    //  the flame just under the bottom of the hull
+    let hull_bottom = 1.333 - 0.06 + (pspr.f_h - pspr.f_w * 0.5) * scale;
+    let fire_y = hull_bottom + (fspr.f_w * 0.5 - fspr.f_h * 0.2) * scale;
+    sprite::draw_sprite_tinted(left, top, width, fspr, xpos, fire_y, 0.0, scale, tint);
+    sprite::draw_sprite_tinted(left, top, width, pspr, xpos, 1.333 - 0.06, 0.0, scale, tint);
+    if let Some(dspr) = ship::damage_sprite(p.ship, p.ships_lost) {
+        let dspr = &assets.sprites[dspr.index];
+        sprite::draw_sprite_tinted(left, top, width, dspr, xpos, 1.333 - 0.06, 0.0, scale, tint);
+    }
+}
+
+fn draw_boss(
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    b: &boss::Boss,
+    assets: &assets::Assets,
+    alpha: f32,
+) {
+    let bspr = &assets.sprites[b.sprite.index];
+    let mut xpos = lerp(b.last_xpos, b.xpos, alpha);
+    let ypos = lerp(b.last_ypos, b.ypos, alpha);
+    let tint = if b.flash_timer > 0.0 {
+        mq::Color::new(1.0, 0.3, 0.3, 1.0)
+    } else {
+        mq::WHITE
+    };
+    if boss::is_dying(b) {
+        //  shudder while it blows up
+        xpos += (b.dying_timer * 70.0).sin() * 0.006;
+    }
+    sprite::draw_sprite_tinted(left, top, width, bspr, xpos, ypos, 0.0, 1.0, tint);
+
+    //  health bar across the top
+    let frac = b.health as f32 / b.max_health as f32;
+    let color = match boss::phase_of(b) {
+        boss::BossPhase::Sweeping => mq::GREEN,
+        boss::BossPhase::Weaving => mq::YELLOW,
+        boss::BossPhase::Frenzy => mq::RED,
+    };
+    mq::draw_rectangle(
+        left + width * 0.2,
+        top + height * 0.06,
+        width * 0.6,
+        height * 0.015,
+        mq::DARKGRAY,
+    );
+    mq::draw_rectangle(
+        left + width * 0.2,
+        top + height * 0.06,
+        width * 0.6 * frac,
+        height * 0.015,
+        color,
+    );
+}
+
+//  The bubble shrinks as the shield runs down, and flickers at the very end
+fn draw_shield(
+    left: f32,
+    top: f32,
+    width: f32,
+    p: &state::Player,
+    assets: &assets::Assets,
+    player_pos_fr: f32,
+) {
+    if p.shield_time < 2.0 && ((p.shield_time * 8.0) as usize % 2) == 1 {
+        return;
+    }
+    let dur = powerup::POWERUP_KINDS
+        .iter()
+        .find(|k| k.effect == powerup::Effect::Shield)
+        .map_or(1.0, |k| k.duration);
+    let n = assets::SHIELD_SPRITES.len();
+    let ix = ((p.shield_time / dur * n as f32) as usize).min(n - 1);
+    let sspr = &assets.sprites[assets::SHIELD_SPRITES[ix].index];
+    sprite::draw_sprite(
+        left,
+        top,
+        width,
+        sspr,
+        player_pos_fr,
+        1.333 - 0.06 - sspr.f_h * 0.1,
+        0.0,
+        1.0,
+    );
+}
+
+//  Icons for a player's running power-ups, with seconds left
+fn draw_active_powerups(
+    left: f32,
+    top: f32,
+    width: f32,
+    height: f32,
+    p: &state::Player,
+    xstart: f32,
+    assets: &assets::Assets,
+) {
+    let mut xpos = xstart;
+    for kind in powerup::POWERUP_KINDS.iter() {
+        let remaining = match kind.effect {
+            powerup::Effect::RapidFire => p.rapid_fire_time,
+            powerup::Effect::SpreadShot => p.spread_shot_time,
+            powerup::Effect::Shield => p.shield_time,
+            powerup::Effect::ExtraLife => 0.0,
+        };
+        if remaining <= 0.0 {
+            continue;
+        }
+        let pspr = &assets.sprites[kind.sprite.index];
+        sprite::draw_sprite(left, top, width, pspr, xpos, 0.02, 0.0, 0.7);
+        mq::draw_text(
+            &format!("{:.0}", remaining.ceil()),
+            left + width * (xpos + 0.015),
+            top + height * 0.03,
+            height * 0.03,
+            mq::WHITE,
+        );
+        xpos += 0.07;
+    }
+}
+
 //  Given width/height, which part of the screen do we draw to?
 fn letterbox(sw: f32, sh: f32) -> (f32, f32, f32, f32) {
     let (ww, wh) = if sh * 0.75 > sw {
@@ -0,0 +1,161 @@
+//  meteor.rs
+
+use crate::assets;
+use crate::rng;
+use crate::sprite;
+
+//  Between some levels there's a meteor shower instead of a formation: rocks
+//  drift down, tumbling, and break into smaller rocks when shot.
+
+//  A shower follows level 2, 5, 8, ...
+const SHOWER_EVERY: i32 = 3;
+pub const SHOWER_METEORS: i32 = 14;
+const SPAWN_MIN_TIME: f32 = 0.6;
+const SPAWN_EXTRA_TIME: f32 = 1.2;
+
+pub struct MeteorSize {
+    pub first: usize, //  first sprite of this size, within a colour
+    pub count: usize,
+    pub hit_points: i32,
+    pub points: i32,
+    pub speed: f32,
+    pub name: &'static str,
+}
+
+//  Biggest first; shooting a rock breaks it into two of the next size down
+pub static SIZES: [MeteorSize; 4] = [
+    MeteorSize {
+        first: 0,
+        count: 4,
+        hit_points: 3,
+        points: 20,
+        speed: 0.12,
+        name: "big",
+    },
+    MeteorSize {
+        first: 4,
+        count: 2,
+        hit_points: 2,
+        points: 40,
+        speed: 0.16,
+        name: "med",
+    },
+    MeteorSize {
+        first: 6,
+        count: 2,
+        hit_points: 1,
+        points: 60,
+        speed: 0.2,
+        name: "small",
+    },
+    MeteorSize {
+        first: 8,
+        count: 2,
+        hit_points: 1,
+        points: 100,
+        speed: 0.24,
+        name: "tiny",
+    },
+];
+
+//  METEOR_SPRITES holds the brown rocks, then the same sizes in grey
+const SPRITES_PER_COLOUR: usize = 10;
+
+#[derive(Clone)]
+pub struct Meteor {
+    pub sprite: &'static sprite::Sprite,
+    pub size: usize, //  into SIZES
+    pub grey: bool,
+    pub xpos: f32,
+    pub ypos: f32,
+    pub last_xpos: f32, //  used for render interpolation
+    pub last_ypos: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub rotation: f32,
+    pub last_rotation: f32,
+    pub spin: f32,
+    pub hit_points: i32,
+    pub dead: bool,
+}
+
+pub fn shower_after(level: i32) -> bool {
+    return level > 0 && level % SHOWER_EVERY == SHOWER_EVERY - 1;
+}
+
+pub fn time_to_next(rng: &mut rng::Rng) -> f32 {
+    return rng::gen_range_f32(rng, SPAWN_MIN_TIME, SPAWN_MIN_TIME + SPAWN_EXTRA_TIME);
+}
+
+fn make(
+    rng: &mut rng::Rng,
+    size: usize,
+    grey: bool,
+    xpos: f32,
+    ypos: f32,
+    vx: f32,
+    vy: f32,
+) -> Meteor {
+    let sz = &SIZES[size];
+    let colour = if grey { SPRITES_PER_COLOUR } else { 0 };
+    let ix = colour + sz.first + rng::gen_range_usize(rng, 0, sz.count);
+    let rotation = rng::gen_range_f32(rng, 0.0, std::f32::consts::TAU);
+    return Meteor {
+        sprite: &assets::METEOR_SPRITES[ix],
+        size: size,
+        grey: grey,
+        xpos: xpos,
+        ypos: ypos,
+        last_xpos: xpos,
+        last_ypos: ypos,
+        vx: vx,
+        vy: vy,
+        rotation: rotation,
+        last_rotation: rotation,
+        spin: rng::gen_range_f32(rng, -2.0, 2.0),
+        hit_points: sz.hit_points,
+        dead: false,
+    };
+}
+
+//  A big rock coming in over the top of the screen
+pub fn new(rng: &mut rng::Rng) -> Meteor {
+    let grey = rng::gen_range_usize(rng, 0, 2) == 1;
+    let xpos = rng::gen_range_f32(rng, 0.1, 0.9);
+    let vx = rng::gen_range_f32(rng, -0.05, 0.05);
+    return make(rng, 0, grey, xpos, -0.1, vx, SIZES[0].speed);
+}
+
+//  Returns true if this hit broke the rock up; see split()
+pub fn hit(m: &mut Meteor) -> bool {
+    m.hit_points -= 1;
+    if m.hit_points <= 0 {
+        m.dead = true;
+        return true;
+    }
+    return false;
+}
+
+//  The pieces a broken rock leaves behind, flying apart; tiny ones just crumble
+pub fn split(rng: &mut rng::Rng, m: &Meteor) -> Vec<Meteor> {
+    let mut ret: Vec<Meteor> = Vec::new();
+    let size = m.size + 1;
+    if size >= SIZES.len() {
+        return ret;
+    }
+    for dir in [-1.0, 1.0] {
+        let vx = m.vx + dir * rng::gen_range_f32(rng, 0.04, 0.1);
+        let vy = SIZES[size].speed * rng::gen_range_f32(rng, 0.8, 1.2);
+        ret.push(make(rng, size, m.grey, m.xpos, m.ypos, vx, vy));
+    }
+    return ret;
+}
+
+pub fn evolve(dt: f32, m: &mut Meteor) {
+    m.xpos += m.vx * dt;
+    m.ypos += m.vy * dt;
+    m.rotation += m.spin * dt;
+    if m.ypos > 1.45 || m.xpos < -0.15 || m.xpos > 1.15 {
+        m.dead = true;
+    }
+}
@@ -0,0 +1,559 @@
+//  netplay.rs
+
+use crate::input;
+use crate::params;
+use crate::rng;
+use crate::ship;
+use crate::sprite;
+use crate::state;
+use crate::versus;
+use quad_net::web_socket::WebSocket;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+//  Co-op over the network, in lockstep: both sides run the whole game from
+//  the same seed, and all they send each other is each tick's input.
+//
+//  Local input goes in INPUT_DELAY ticks ahead of when it's used, which
+//  gives it that long to reach the partner. When the partner's input for a
+//  tick still isn't in, the game guesses they're holding whatever they held
+//  last and runs on. Alongside the game on screen sits a confirmed copy,
+//  run only as far as both sides' inputs are known; when an input turns up
+//  that doesn't match the guess, the game on screen is thrown away and
+//  re-run from the confirmed copy. That's the rollback.
+//  Only the confirmed copy reports telemetry and high scores; sounds follow
+//  the game on screen, so a rolled-back shot can be heard twice.
+//
+//  Both sides send a checksum of the confirmed game now and then, so a
+//  desync (the two games having drifted apart) shows up as soon as it
+//  happens rather than as a partner's ship doing odd things.
+//
+//  Sessions talk through the relay (src/bin/relay.rs) over WebSocket, or,
+//  for testing, through a Loopback pair that can be made slow and lossy.
+
+//  In ticks; 4 is a frame or two, which nobody notices
+pub const INPUT_DELAY: usize = 4;
+//  How far the game may run on guesses before it waits for the partner
+const MAX_AHEAD: usize = 36;
+//  Ticks between checksums
+const CHECK_INTERVAL: usize = 60;
+//  Inputs in one packet; anything past that goes in the next
+const MAX_INPUTS_SENT: usize = 255;
+
+//  Tags for netplay's messages; the relay passes them on as they are
+const TAG_INPUTS: u8 = 16;
+const TAG_JOIN: u8 = 17;
+
+//  Hands packets to the far end after a delay, dropping some on the way
+pub struct Loopback {
+    clock: f32,
+    latency: f32,
+    loss: f32, //  chance of a packet going missing, 0 to 1
+    rng: rng::Rng,
+    inbox: Inbox, //  with when each arrives
+    far_inbox: Inbox,
+}
+
+pub enum Transport {
+    Socket(WebSocket),
+    Loopback(Loopback),
+}
+
+type Inbox = Rc<RefCell<Vec<(f32, Vec<u8>)>>>;
+
+fn loopback_end(inbox: &Inbox, far_inbox: &Inbox, latency: f32, loss: f32, seed: u64) -> Transport {
+    return Transport::Loopback(Loopback {
+        clock: 0.0,
+        latency: latency,
+        loss: loss,
+        rng: rng::new(seed),
+        inbox: inbox.clone(),
+        far_inbox: far_inbox.clone(),
+    });
+}
+
+//  Two ends joined to each other; latency is one way, in seconds
+pub fn loopback_pair(latency: f32, loss: f32, seed: u64) -> (Transport, Transport) {
+    let a: Inbox = Rc::new(RefCell::new(Vec::new()));
+    let b: Inbox = Rc::new(RefCell::new(Vec::new()));
+    return (
+        loopback_end(&a, &b, latency, loss, seed),
+        loopback_end(&b, &a, latency, loss, seed + 1),
+    );
+}
+
+//  quad-net's desktop WebSocket panics if it can't connect, so make sure
+//  the relay is there first. A browser just never opens the socket; see
+//  is_connected().
+pub fn connect() -> Result<Transport, String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        let addr = versus::RELAY_WS_ADDR;
+        std::net::TcpStream::connect(addr)
+            .map_err(|e| format!("can't reach the relay at {}: {}", addr, e))?;
+    }
+    let socket = WebSocket::connect(versus::RELAY_WS_URL)
+        .map_err(|e| format!("can't reach the relay: {:?}", e))?;
+    return Ok(Transport::Socket(socket));
+}
+
+//  Always so on the desktop; a browser's socket opens in the background,
+//  and nothing may be sent before it has
+pub fn is_connected(transport: &Transport) -> bool {
+    match transport {
+        Transport::Socket(socket) => return socket.connected(),
+        Transport::Loopback(_) => return true,
+    }
+}
+
+//  see versus::browser_try_recv() for why the web build doesn't ask quad-net
+#[cfg(not(target_arch = "wasm32"))]
+fn socket_try_recv(socket: &mut WebSocket) -> Option<Vec<u8>> {
+    return socket.try_recv();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn socket_try_recv(_socket: &mut WebSocket) -> Option<Vec<u8>> {
+    return versus::browser_try_recv();
+}
+
+pub fn send_packet(transport: &mut Transport, data: &[u8]) {
+    match transport {
+        Transport::Socket(socket) => socket.send_bytes(data),
+        Transport::Loopback(lb) => {
+            if rng::gen_range_f32(&mut lb.rng, 0.0, 1.0) >= lb.loss {
+                let arrives = lb.clock + lb.latency;
+                lb.far_inbox.borrow_mut().push((arrives, data.to_vec()));
+            }
+        }
+    }
+}
+
+pub fn receive_packet(transport: &mut Transport) -> Option<Vec<u8>> {
+    match transport {
+        Transport::Socket(socket) => return socket_try_recv(socket),
+        Transport::Loopback(lb) => {
+            let mut inbox = lb.inbox.borrow_mut();
+            if inbox
+                .first()
+                .is_some_and(|(arrives, _)| *arrives <= lb.clock)
+            {
+                return Some(inbox.remove(0).1);
+            }
+            return None;
+        }
+    }
+}
+
+//  A loopback's clock only moves when it's told; sockets keep their own time
+pub fn advance_clock(transport: &mut Transport, delta_time: f32) {
+    if let Transport::Loopback(lb) = transport {
+        lb.clock += delta_time;
+    }
+}
+
+//  Ask the relay for a partner; poll receive_start() for the answer
+pub fn find_partner(transport: &mut Transport) {
+    let hello = versus::Message::Hello(versus::Game::CoOp);
+    send_packet(transport, &versus::encode(&hello));
+}
+
+//  The seed to play, and which player this side is
+pub fn receive_start(transport: &mut Transport) -> Option<(u64, usize)> {
+    while let Some(data) = receive_packet(transport) {
+        if let Ok(versus::Message::Start(seed, seat)) = versus::decode(&data) {
+            return Some((seed, seat as usize));
+        }
+    }
+    return None;
+}
+
+//  Before a game both sides say who they are: their ship and name
+pub fn send_join(transport: &mut Transport, ship: ship::Ship, name: &str) {
+    let mut data = vec![TAG_JOIN, ship.hull as u8, ship.colour as u8];
+    data.extend_from_slice(name.as_bytes());
+    send_packet(transport, &data);
+}
+
+pub fn receive_join(transport: &mut Transport) -> Option<(ship::Ship, String)> {
+    while let Some(data) = receive_packet(transport) {
+        if data.len() >= 3 && data[0] == TAG_JOIN {
+            let ship = ship::Ship {
+                hull: data[1] as usize,
+                colour: data[2] as usize,
+            };
+            return Some((ship, String::from_utf8_lossy(&data[3..]).to_string()));
+        }
+    }
+    return None;
+}
+
+pub struct Session {
+    pub local: usize, //  which player this side is
+    pub tick: usize,  //  ticks the game on screen has run
+
+    //  by tick; local input runs INPUT_DELAY ahead of the game
+    pub local_inputs: Vec<input::Input>,
+    pub remote_inputs: Vec<input::Input>, //  as far as the partner has sent
+    //  what the game on screen took the partner's input to be, by tick
+    guessed: Vec<input::Input>,
+    remote_acked: usize, //  how many local inputs the partner has
+
+    pub confirmed: state::State, //  as of confirmed_tick, on known inputs only
+    pub confirmed_tick: usize,
+    misguessed: bool,
+
+    local_checks: Vec<(usize, u32)>, //  (tick, checksum) of the confirmed game
+    remote_checks: Vec<(usize, u32)>,
+
+    pub rollbacks: usize,
+    pub ticks_rerun: usize,
+    pub desync_tick: Option<usize>, //  first checksum that didn't match
+    pub partner_left: bool,
+}
+
+//  The game on screen becomes a guess; the confirmed copy is the real one
+pub fn new(local: usize, state: &mut state::State) -> Session {
+    let confirmed = state.clone();
+    state.quiet = true;
+    return Session {
+        local: local,
+        tick: 0,
+        local_inputs: vec![input::Input::default(); INPUT_DELAY],
+        remote_inputs: vec![input::Input::default(); INPUT_DELAY],
+        guessed: Vec::new(),
+        remote_acked: 0,
+        confirmed: confirmed,
+        confirmed_tick: 0,
+        misguessed: false,
+        local_checks: Vec::new(),
+        remote_checks: Vec::new(),
+        rollbacks: 0,
+        ticks_rerun: 0,
+        desync_tick: None,
+        partner_left: false,
+    };
+}
+
+//  Inputs in player order, as update_state() wants them
+pub fn arrange(session: &Session, local: input::Input, remote: input::Input) -> Vec<input::Input> {
+    if session.local == 0 {
+        return vec![local, remote];
+    }
+    return vec![remote, local];
+}
+
+//  Enough of the game to tell whether two copies have drifted apart
+pub fn checksum(state: &state::State) -> u32 {
+    let mut hash: u32 = 0x811c9dc5;
+    let mut mix = |v: u32| {
+        hash = (hash ^ v).wrapping_mul(0x01000193);
+    };
+    mix(state.score as u32);
+    mix(state.current_level as u32);
+    mix(rng::next_u32(&mut state.rng.clone()));
+    for p in state.players.iter() {
+        mix(p.pos_fr.to_bits());
+        mix(p.lives as u32);
+        mix(p.score as u32);
+    }
+    for alien in state.aliens.iter() {
+        mix(alien.xpos.to_bits());
+        mix(alien.ypos.to_bits());
+        mix(alien.dead as u32);
+    }
+    for bullet in state.bullets.iter() {
+        mix(bullet.xpos.to_bits());
+        mix(bullet.ypos.to_bits());
+    }
+    for bomb in state.bombs.iter() {
+        mix(bomb.xpos.to_bits());
+        mix(bomb.ypos.to_bits());
+    }
+    return hash;
+}
+
+fn guess_remote(session: &Session, tick: usize) -> input::Input {
+    return match session.remote_inputs.get(tick) {
+        Some(input) => *input,
+        None => *session.remote_inputs.last().unwrap(),
+    };
+}
+
+fn compare_checks(session: &mut Session) {
+    for (tick, theirs) in session.remote_checks.iter() {
+        let ours = session.local_checks.iter().find(|(t, _)| t == tick);
+        if let Some((_, ours)) = ours {
+            if ours != theirs && session.desync_tick.is_none() {
+                session.desync_tick = Some(*tick);
+            }
+        }
+    }
+    //  a checksum not matched up by now never will be
+    let newest = session.local_checks.last().map_or(0, |(t, _)| *t);
+    session
+        .remote_checks
+        .retain(|(t, _)| *t > newest.saturating_sub(CHECK_INTERVAL * 8));
+    session
+        .local_checks
+        .retain(|(t, _)| *t > newest.saturating_sub(CHECK_INTERVAL * 8));
+}
+
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    return u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]);
+}
+
+//  Packet layout, integers little endian: tag, how many of the receiver's
+//  inputs the sender has (u32), the first tick sent (u32), how many (u8),
+//  the input bits for each, then the sender's newest checksum: tick (u32)
+//  and value (u32). Every packet repeats all that hasn't been acknowledged,
+//  so a lost one costs nothing but time.
+fn take_packet(session: &mut Session, data: &[u8]) {
+    if data == versus::encode(&versus::Message::OpponentLeft).as_slice() {
+        session.partner_left = true;
+        return;
+    }
+    if data.len() < 10 || data[0] != TAG_INPUTS {
+        return;
+    }
+    let acked = read_u32(data, 1) as usize;
+    let first = read_u32(data, 5) as usize;
+    let count = data[9] as usize;
+    //  nobody honest acknowledges inputs that haven't been sent yet
+    if data.len() < 10 + count + 8 || acked > session.local_inputs.len() {
+        return;
+    }
+    session.remote_acked = session.remote_acked.max(acked);
+
+    for ix in 0..count {
+        let tick = first + ix;
+        if tick == session.remote_inputs.len() {
+            let input = input::from_bits(data[10 + ix]);
+            if tick < session.tick && session.guessed[tick] != input {
+                session.misguessed = true;
+            }
+            session.remote_inputs.push(input);
+        }
+    }
+
+    let check_tick = read_u32(data, 10 + count) as usize;
+    let check = read_u32(data, 14 + count);
+    if check_tick > 0 {
+        session.remote_checks.push((check_tick, check));
+    }
+}
+
+//  Once a frame, before the ticks: take in what the partner sent, bring the
+//  confirmed game up to date, and roll back if a guess was wrong
+pub fn receive(
+    session: &mut Session,
+    transport: &mut Transport,
+    state: &mut state::State,
+    delta_time: f32,
+    hitboxes: &[sprite::Hitbox],
+) {
+    advance_clock(transport, delta_time);
+    while let Some(data) = receive_packet(transport) {
+        take_packet(session, &data);
+    }
+    if session.partner_left {
+        //  nobody's there now; their ship sits still
+        while session.remote_inputs.len() < session.local_inputs.len() {
+            if session.remote_inputs.len() < session.tick
+                && session.guessed[session.remote_inputs.len()] != input::Input::default()
+            {
+                session.misguessed = true;
+            }
+            session.remote_inputs.push(input::Input::default());
+        }
+    }
+
+    let known = session
+        .remote_inputs
+        .len()
+        .min(session.local_inputs.len())
+        .min(session.tick);
+    while session.confirmed_tick < known {
+        let t = session.confirmed_tick;
+        let inputs = arrange(session, session.local_inputs[t], session.remote_inputs[t]);
+        state::update_state(params::TICK_TIME, &mut session.confirmed, &inputs, hitboxes);
+        session.confirmed_tick += 1;
+        if session.confirmed_tick % CHECK_INTERVAL == 0 {
+            let check = checksum(&session.confirmed);
+            session.local_checks.push((session.confirmed_tick, check));
+        }
+    }
+    compare_checks(session);
+
+    if session.misguessed {
+        session.misguessed = false;
+        session.rollbacks += 1;
+        *state = session.confirmed.clone();
+        state.quiet = true;
+        for t in session.confirmed_tick..session.tick {
+            let remote = guess_remote(session, t);
+            session.guessed[t] = remote;
+            let inputs = arrange(session, session.local_inputs[t], remote);
+            state::update_state(params::TICK_TIME, state, &inputs, hitboxes);
+            session.ticks_rerun += 1;
+        }
+    }
+}
+
+//  Run the game on screen one tick, with this tick's local input going in
+//  to be used INPUT_DELAY ticks from now. False when it's too far ahead of
+//  the partner and has to wait.
+pub fn tick(
+    session: &mut Session,
+    state: &mut state::State,
+    local: input::Input,
+    hitboxes: &[sprite::Hitbox],
+) -> bool {
+    if session.tick >= session.confirmed_tick + MAX_AHEAD {
+        return false;
+    }
+    session.local_inputs.push(local);
+    let t = session.tick;
+    let remote = guess_remote(session, t);
+    session.guessed.push(remote);
+    let inputs = arrange(session, session.local_inputs[t], remote);
+    state::update_state(params::TICK_TIME, state, &inputs, hitboxes);
+    session.tick += 1;
+    return true;
+}
+
+//  Once a frame, after the ticks
+pub fn send(session: &mut Session, transport: &mut Transport) {
+    let first = session.remote_acked.min(session.local_inputs.len());
+    let count = (session.local_inputs.len() - first).min(MAX_INPUTS_SENT);
+    let mut data = vec![TAG_INPUTS];
+    data.extend_from_slice(&(session.remote_inputs.len() as u32).to_le_bytes());
+    data.extend_from_slice(&(first as u32).to_le_bytes());
+    data.push(count as u8);
+    for input in session.local_inputs[first..first + count].iter() {
+        data.push(input::to_bits(input));
+    }
+    let (check_tick, check) = session.local_checks.last().copied().unwrap_or((0, 0));
+    data.extend_from_slice(&(check_tick as u32).to_le_bytes());
+    data.extend_from_slice(&check.to_le_bytes());
+    send_packet(transport, &data);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets;
+    use crate::wave;
+
+    struct Side {
+        transport: Transport,
+        session: Session,
+        state: state::State,
+        rng: rng::Rng,
+        held: input::Input,
+    }
+
+    fn new_side(local: usize, transport: Transport, waves: &Rc<Vec<wave::Wave>>) -> Side {
+        let ships = [ship::DEFAULT_SHIP, ship::DEFAULT_SHIP];
+        let mut state = state::new_game_state(1, waves, &ships);
+        let session = new(local, &mut state);
+        return Side {
+            transport: transport,
+            session: session,
+            state: state,
+            rng: rng::new(10 + local as u64),
+            held: input::Input::default(),
+        };
+    }
+
+    //  Two frames' worth of ticks a frame, up to tick number until
+    fn frame(side: &mut Side, hitboxes: &[sprite::Hitbox], until: usize) {
+        receive(
+            &mut side.session,
+            &mut side.transport,
+            &mut side.state,
+            params::TICK_TIME * 2.0,
+            hitboxes,
+        );
+        for _ in 0..2 {
+            if rng::gen_range_usize(&mut side.rng, 0, 20) == 0 {
+                let way = rng::gen_range_usize(&mut side.rng, 0, 3);
+                side.held.left = way == 0;
+                side.held.right = way == 1;
+                side.held.fire = !side.held.fire;
+            }
+            if side.session.tick < until {
+                tick(&mut side.session, &mut side.state, side.held, hitboxes);
+            }
+        }
+        send(&mut side.session, &mut side.transport);
+    }
+
+    //  Both sides, and a straight run of the same inputs, end up the same
+    fn converges(latency: f32, loss: f32) {
+        let hitboxes = assets::load_hitboxes();
+        let waves = Rc::new(Vec::new());
+        let ticks = 20 * params::TICK_RATE as usize;
+        let (a, b) = loopback_pair(latency, loss, 1);
+        let mut sides = [new_side(0, a, &waves), new_side(1, b, &waves)];
+        let mut frames = 0;
+        while sides.iter().any(|s| s.session.confirmed_tick < ticks) {
+            for side in sides.iter_mut() {
+                frame(side, &hitboxes, ticks);
+            }
+            frames += 1;
+            assert!(frames < ticks * 4, "never caught up");
+        }
+
+        let ships = [ship::DEFAULT_SHIP, ship::DEFAULT_SHIP];
+        let mut straight = state::new_game_state(1, &waves, &ships);
+        for t in 0..ticks {
+            let inputs = [
+                sides[0].session.local_inputs[t],
+                sides[1].session.local_inputs[t],
+            ];
+            state::update_state(params::TICK_TIME, &mut straight, &inputs, &hitboxes);
+        }
+        let expected = checksum(&straight);
+        for side in sides.iter() {
+            assert_eq!(checksum(&side.session.confirmed), expected);
+            assert_eq!(checksum(&side.state), expected);
+            assert!(side.session.desync_tick.is_none());
+            if latency > 0.0 {
+                assert!(side.session.rollbacks > 0);
+            }
+        }
+    }
+
+    #[test]
+    fn converges_without_lag() {
+        converges(0.0, 0.0);
+    }
+
+    #[test]
+    fn converges_through_lag_and_loss() {
+        converges(0.08, 0.1);
+        converges(0.15, 0.3);
+    }
+
+    #[test]
+    fn ignores_acks_for_inputs_never_sent() {
+        let mut state = state::new_game_state(1, &Rc::new(Vec::new()), &[ship::DEFAULT_SHIP; 2]);
+        let mut session = new(0, &mut state);
+        let (mut a, _b) = loopback_pair(0.0, 0.0, 1);
+        let mut data = vec![TAG_INPUTS];
+        data.extend_from_slice(&u32::MAX.to_le_bytes());
+        data.extend_from_slice(&[0; 4 + 1 + 8]);
+        take_packet(&mut session, &data);
+        assert_eq!(session.remote_acked, 0);
+        send(&mut session, &mut a);
+    }
+
+    #[test]
+    fn loopback_needs_no_waiting_for() {
+        //  what the game polls before its first Hello
+        let (a, b) = loopback_pair(0.15, 0.3, 1);
+        assert!(is_connected(&a));
+        assert!(is_connected(&b));
+    }
+}
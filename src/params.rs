@@ -1,4 +1,7 @@
-pub static VERSION : &str = "0.1.0";
+//  Replays only play back under the version they were recorded with, so
+//  this goes up with any change that makes the same inputs play out
+//  differently, however small
+pub static VERSION : &str = "0.2.0";
 
 pub const HORIZ_SPEED : f32 = 1.0;
 pub const VERT_SPEED : f32 = 4.0;
@@ -6,3 +9,7 @@ pub const RIGHT_MARGIN : f32 = 0.96;
 pub const LEFT_MARGIN : f32 = 0.04;
 pub const DOWN_DISTANCE : f32 = 0.04;
 pub const PHASE_SPEED : f32 = 30.0;
+
+//  The simulation always steps by exactly this much, whatever the display rate
+pub const TICK_RATE : f32 = 120.0;
+pub const TICK_TIME : f32 = 1.0 / TICK_RATE;
@@ -0,0 +1,91 @@
+//  powerup.rs
+
+use crate::assets;
+use crate::rng;
+use crate::sprite;
+
+//  Chance that a destroyed alien leaves a power-up behind
+const DROP_CHANCE: f32 = 0.06;
+const FALL_SPEED: f32 = 0.25;
+
+//  Rapid fire multiplies the time between shots by this
+pub const RAPID_FIRE_FACTOR: f32 = 0.4;
+//  Spread shot fires three bullets; the outer two drift sideways this fast
+pub const SPREAD_VELOCITY: f32 = 0.12;
+pub const MAX_LIVES: i32 = 5;
+
+#[derive(PartialEq, Clone, Copy)]
+pub enum Effect {
+    RapidFire,
+    SpreadShot,
+    Shield,
+    ExtraLife,
+}
+
+pub struct PowerupKind {
+    pub sprite: &'static sprite::Sprite,
+    pub effect: Effect,
+    pub duration: f32, //  seconds; 0 for instant effects
+    pub name: &'static str,
+}
+
+pub static POWERUP_KINDS: [PowerupKind; 4] = [
+    PowerupKind {
+        sprite: &assets::POWERUP_BOLT_SPRITE,
+        effect: Effect::RapidFire,
+        duration: 10.0,
+        name: "rapid_fire",
+    },
+    PowerupKind {
+        sprite: &assets::POWERUP_STAR_SPRITE,
+        effect: Effect::SpreadShot,
+        duration: 8.0,
+        name: "spread_shot",
+    },
+    PowerupKind {
+        sprite: &assets::POWERUP_SHIELD_SPRITE,
+        effect: Effect::Shield,
+        duration: 12.0,
+        name: "shield",
+    },
+    PowerupKind {
+        sprite: &assets::POWERUP_PILL_SPRITE,
+        effect: Effect::ExtraLife,
+        duration: 0.0,
+        name: "extra_life",
+    },
+];
+
+#[derive(Clone)]
+pub struct Powerup {
+    pub kind: &'static PowerupKind,
+    pub xpos: f32,
+    pub ypos: f32,
+    pub last_ypos: f32, //  used for render interpolation
+    pub phase: f32,
+    pub dead: bool,
+}
+
+//  Called for each destroyed alien; usually there's nothing
+pub fn maybe_drop(rng: &mut rng::Rng, xpos: f32, ypos: f32) -> Option<Powerup> {
+    if rng::gen_range_f32(rng, 0.0, 1.0) >= DROP_CHANCE {
+        return None;
+    }
+    let ix = rng::gen_range_usize(rng, 0, POWERUP_KINDS.len());
+    return Some(Powerup {
+        kind: &POWERUP_KINDS[ix],
+        xpos: xpos,
+        ypos: ypos,
+        last_ypos: ypos,
+        phase: 0.0,
+        dead: false,
+    });
+}
+
+pub fn evolve(dt: f32, p: &mut Powerup) {
+    p.ypos += FALL_SPEED * dt;
+    p.phase += dt * 4.0;
+    if p.ypos > 1.333 {
+        p.dead = true;
+    }
+}
@@ -0,0 +1,326 @@
+//  replay.rs
+
+use crate::input;
+#[cfg(target_arch = "wasm32")]
+use crate::js::JsObject;
+use crate::params;
+use crate::ship;
+use crate::sprite;
+use crate::state;
+use crate::wave;
+use std::rc::Rc;
+
+//  A game is fully determined by its seed, the ship flown and the input of
+//  every tick, so that is all a replay holds. The score is what the
+//  player's client claimed when the game ended; it's used to pick the best
+//  run and to check against.
+pub struct Replay {
+    pub version: String,
+    pub seed: u64,
+    pub ship: ship::Ship,
+    pub score: i32,
+    pub inputs: Vec<input::Input>,
+}
+
+//  File layout, all integers little endian:
+//  magic "SORP", version length u8, version bytes, seed u64, hull u8,
+//  colour u8, score i32,
+//  tick count u32, then (input bits u8, repeat count u8) runs until all
+//  ticks are covered. Input changes rarely, so runs keep files small.
+const MAGIC: &[u8; 4] = b"SORP";
+
+pub fn new(seed: u64, ship: ship::Ship) -> Replay {
+    return Replay {
+        version: params::VERSION.to_string(),
+        seed: seed,
+        ship: ship,
+        score: 0,
+        inputs: Vec::new(),
+    };
+}
+
+pub fn record(replay: &mut Replay, input: &input::Input) {
+    replay.inputs.push(*input);
+}
+
+pub fn encode(replay: &Replay) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::new();
+    ret.extend_from_slice(MAGIC);
+    ret.push(replay.version.len() as u8);
+    ret.extend_from_slice(replay.version.as_bytes());
+    ret.extend_from_slice(&replay.seed.to_le_bytes());
+    ret.push(replay.ship.hull as u8);
+    ret.push(replay.ship.colour as u8);
+    ret.extend_from_slice(&replay.score.to_le_bytes());
+    ret.extend_from_slice(&(replay.inputs.len() as u32).to_le_bytes());
+    let mut ix = 0;
+    while ix < replay.inputs.len() {
+        let bits = input::to_bits(&replay.inputs[ix]);
+        let mut count = 1;
+        while count < 255
+            && ix + count < replay.inputs.len()
+            && input::to_bits(&replay.inputs[ix + count]) == bits
+        {
+            count += 1;
+        }
+        ret.push(bits);
+        ret.push(count as u8);
+        ix += count;
+    }
+    return ret;
+}
+
+fn take<'a>(data: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], String> {
+    if *pos + n > data.len() {
+        return Err(format!("replay truncated at byte {}", *pos));
+    }
+    let ret = &data[*pos..*pos + n];
+    *pos += n;
+    return Ok(ret);
+}
+
+pub fn decode(data: &[u8]) -> Result<Replay, String> {
+    let mut pos = 0;
+    if take(data, &mut pos, 4)? != MAGIC {
+        return Err("not a replay file".to_string());
+    }
+    let vlen = take(data, &mut pos, 1)?[0] as usize;
+    let version = String::from_utf8_lossy(take(data, &mut pos, vlen)?).to_string();
+    if version != params::VERSION {
+        return Err(format!(
+            "replay is from version {}, this is version {}",
+            version,
+            params::VERSION
+        ));
+    }
+    let mut u64b = [0u8; 8];
+    u64b.copy_from_slice(take(data, &mut pos, 8)?);
+    let shipb = take(data, &mut pos, 2)?;
+    let ship = ship::Ship {
+        hull: shipb[0] as usize,
+        colour: shipb[1] as usize,
+    };
+    let mut i32b = [0u8; 4];
+    i32b.copy_from_slice(take(data, &mut pos, 4)?);
+    let mut u32b = [0u8; 4];
+    u32b.copy_from_slice(take(data, &mut pos, 4)?);
+    let ticks = u32::from_le_bytes(u32b) as usize;
+    //  the count comes from the file, so it's only believed as far as the
+    //  runs left could possibly stretch
+    if ticks > (data.len() - pos) / 2 * 255 {
+        return Err(format!("replay claims {} ticks but is too short", ticks));
+    }
+
+    let mut inputs: Vec<input::Input> = Vec::with_capacity(ticks);
+    while inputs.len() < ticks {
+        let run = take(data, &mut pos, 2)?;
+        for _ in 0..run[1] {
+            inputs.push(input::from_bits(run[0]));
+        }
+    }
+    if inputs.len() != ticks {
+        return Err("replay input runs overrun the tick count".to_string());
+    }
+    return Ok(Replay {
+        version: version,
+        seed: u64::from_le_bytes(u64b),
+        ship: ship,
+        score: i32::from_le_bytes(i32b),
+        inputs: inputs,
+    });
+}
+
+//  Storage: files next to the executable's working directory on native,
+//  localStorage (through space-observers.js) in the browser.
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn save_replay(kind: JsObject, data: JsObject);
+    fn load_replay(kind: JsObject) -> JsObject;
+}
+
+#[cfg(target_arch = "wasm32")]
+fn store(kind: &str, data: &[u8]) {
+    unsafe {
+        save_replay(JsObject::string(kind), JsObject::buffer(data));
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fetch(kind: &str) -> Option<Vec<u8>> {
+    let obj = unsafe { load_replay(JsObject::string(kind)) };
+    if obj.is_nil() {
+        return None;
+    }
+    let mut ret: Vec<u8> = Vec::new();
+    obj.to_byte_buffer(&mut ret);
+    return Some(ret);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn store(kind: &str, data: &[u8]) {
+    if let Err(e) = std::fs::write(format!("{}.replay", kind), data) {
+        macroquad::logging::warn!("could not save {} replay: {}", kind, e);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch(kind: &str) -> Option<Vec<u8>> {
+    return std::fs::read(format!("{}.replay", kind)).ok();
+}
+
+pub fn load_file(path: &str) -> Result<Replay, String> {
+    let data = std::fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+    return decode(&data);
+}
+
+//  Keep the most recent run, and the best run seen so far
+pub fn save(replay: &Replay) {
+    let data = encode(replay);
+    store("last", &data);
+    let beaten = match load_best() {
+        Some(best) => replay.score > best.score,
+        None => true,
+    };
+    if beaten {
+        store("best", &data);
+    }
+}
+
+pub fn load_last() -> Option<Replay> {
+    return fetch("last").and_then(|data| decode(&data).ok());
+}
+
+pub fn load_best() -> Option<Replay> {
+    return fetch("best").and_then(|data| decode(&data).ok());
+}
+
+//  The leaderboard's top run, for racing against. The web page downloads
+//  it when it loads; natively it's a top.replay put there by hand.
+pub fn load_top() -> Option<Replay> {
+    return fetch("top").and_then(|data| decode(&data).ok());
+}
+
+//  Re-run a replay from its seed without a window, stopping at game over
+pub fn simulate(
+    replay: &Replay,
+    hitboxes: &[sprite::Hitbox],
+    waves: &Rc<Vec<wave::Wave>>,
+) -> state::State {
+    let mut st = state::new_game_state(replay.seed, waves, &[replay.ship]);
+    for input in replay.inputs.iter() {
+        if state::is_game_over(&st) {
+            break;
+        }
+        state::update_state(params::TICK_TIME, &mut st, &[*input], hitboxes);
+    }
+    return st;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets;
+
+    fn sample() -> Replay {
+        let mut rp = new(42, ship::Ship { hull: 1, colour: 3 });
+        rp.score = 1230;
+        //  long runs, to cross the 255 limit, and short ones
+        for ix in 0..1000 {
+            let bits = if ix < 600 { 4 } else { (ix % 7) as u8 };
+            record(&mut rp, &input::from_bits(bits));
+        }
+        return rp;
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let rp = sample();
+        let back = decode(&encode(&rp)).unwrap();
+        assert_eq!(back.version, rp.version);
+        assert_eq!(back.seed, rp.seed);
+        assert_eq!(back.ship.hull, rp.ship.hull);
+        assert_eq!(back.ship.colour, rp.ship.colour);
+        assert_eq!(back.score, rp.score);
+        assert!(back.inputs == rp.inputs);
+    }
+
+    #[test]
+    fn truncated_replay_is_rejected() {
+        let data = encode(&sample());
+        for len in 0..data.len() {
+            assert!(decode(&data[..len]).is_err(), "length {}", len);
+        }
+    }
+
+    //  where the tick count sits: after magic, version, seed, ship and score
+    fn tick_count_at(rp: &Replay) -> usize {
+        return 4 + 1 + rp.version.len() + 8 + 2 + 4;
+    }
+
+    #[test]
+    fn huge_tick_count_is_rejected() {
+        //  a bare header, as someone might hand-craft to knock the checker over
+        let rp = new(0, ship::DEFAULT_SHIP);
+        let mut data = encode(&rp);
+        let at = tick_count_at(&rp);
+        data[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode(&data).is_err());
+        //  and one with a few runs after it
+        let rp = sample();
+        let mut data = encode(&rp);
+        let at = tick_count_at(&rp);
+        data[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(decode(&data).is_err());
+    }
+
+    #[test]
+    fn hostile_upload_fails_to_load() {
+        //  what verify_replay reads; an Err there is its exit status 2
+        let rp = new(1, ship::DEFAULT_SHIP);
+        let mut data = encode(&rp);
+        let at = tick_count_at(&rp);
+        data[at..at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let path = std::env::temp_dir().join(format!("hostile-{}.replay", std::process::id()));
+        std::fs::write(&path, &data).unwrap();
+        let loaded = load_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    fn other_version_is_rejected() {
+        let mut rp = sample();
+        rp.version = "0.0.1".to_string();
+        assert!(decode(&encode(&rp)).is_err());
+        let mut data = encode(&sample());
+        data[0] = b'X';
+        assert!(decode(&data).is_err());
+    }
+
+    #[test]
+    fn simulate_reproduces_the_recorded_game() {
+        let hitboxes = assets::load_hitboxes();
+        let waves = Rc::new(Vec::new());
+        let mut rp = new(7, ship::DEFAULT_SHIP);
+        let mut st = state::new_game_state(rp.seed, &waves, &[rp.ship]);
+        let mut tick = 0;
+        while !state::is_game_over(&st) && tick < 300 * params::TICK_RATE as usize {
+            let input = input::Input {
+                left: tick % 400 < 150,
+                right: tick % 400 >= 250,
+                fire: tick % 3 != 0,
+                pause: false,
+            };
+            record(&mut rp, &input);
+            state::update_state(params::TICK_TIME, &mut st, &[input], &hitboxes);
+            tick += 1;
+        }
+        rp.score = st.score;
+        let back = decode(&encode(&rp)).unwrap();
+        let again = simulate(&back, &hitboxes, &waves);
+        assert_eq!(again.score, rp.score);
+        assert_eq!(again.current_level, st.current_level);
+        assert_eq!(state::is_game_over(&again), state::is_game_over(&st));
+    }
+}
@@ -0,0 +1,44 @@
+//  rng.rs
+
+//  Per-game random number generator (xorshift64*). All gameplay randomness
+//  goes through this, so the same seed and the same inputs play out the
+//  same game every time. Don't use macroquad::rand in the simulation.
+#[derive(Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+pub fn new(seed: u64) -> Rng {
+    //  splitmix the seed so small/similar seeds still start far apart,
+    //  and so the state is never zero
+    let mut z = seed.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z = z ^ (z >> 31);
+    return Rng {
+        state: if z == 0 { 1 } else { z },
+    };
+}
+
+pub fn next_u32(rng: &mut Rng) -> u32 {
+    let mut x = rng.state;
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    rng.state = x;
+    return (x.wrapping_mul(0x2545f4914f6cdd1d) >> 32) as u32;
+}
+
+//  [low, high)
+pub fn gen_range_f32(rng: &mut Rng, low: f32, high: f32) -> f32 {
+    let unit = (next_u32(rng) >> 8) as f32 / (1u32 << 24) as f32;
+    return low + (high - low) * unit;
+}
+
+//  [low, high); returns low for an empty range
+pub fn gen_range_usize(rng: &mut Rng, low: usize, high: usize) -> usize {
+    if high <= low {
+        return low;
+    }
+    return low + (next_u32(rng) as usize) % (high - low);
+}
@@ -0,0 +1,89 @@
+//  ship.rs
+
+use crate::assets;
+use crate::sprite;
+
+//  The player picks one of three hulls, in one of four colours. Colour is
+//  just paint; the hull decides how the ship handles.
+pub struct Hull {
+    pub name: &'static str,
+    pub player_speed: f32,
+    pub firing_duration: f32, //  cooldown between shots on level 1
+    pub fire_velocity: f32,
+    pub hitbox_scale: f32, //  how much of the sprite counts when hit
+}
+
+pub static HULLS: [Hull; 3] = [
+    //  quick and slim, but slow to reload
+    Hull {
+        name: "Interceptor",
+        player_speed: 0.38,
+        firing_duration: 0.95,
+        fire_velocity: 1.2,
+        hitbox_scale: 0.85,
+    },
+    //  wide and sluggish, but it fires fast
+    Hull {
+        name: "Gunship",
+        player_speed: 0.24,
+        firing_duration: 0.6,
+        fire_velocity: 1.0,
+        hitbox_scale: 1.0,
+    },
+    //  the original all-rounder
+    Hull {
+        name: "Striker",
+        player_speed: 0.3,
+        firing_duration: 0.8,
+        fire_velocity: 1.1,
+        hitbox_scale: 1.0,
+    },
+];
+
+pub static COLOURS: [&str; 4] = ["blue", "green", "orange", "red"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Ship {
+    pub hull: usize,
+    pub colour: usize,
+}
+
+//  What the game played before there was a choice
+pub const DEFAULT_SHIP: Ship = Ship { hull: 2, colour: 0 };
+
+pub fn hull(ship: Ship) -> &'static Hull {
+    return &HULLS[ship.hull];
+}
+
+pub fn sprite(ship: Ship) -> &'static sprite::Sprite {
+    return &assets::PLAYER_SHIPS[ship.hull * COLOURS.len() + ship.colour];
+}
+
+//  Out-of-range values (say, from a tampered replay) get the default
+pub fn sanitize(ship: Ship) -> Ship {
+    if ship.hull >= HULLS.len() || ship.colour >= COLOURS.len() {
+        return DEFAULT_SHIP;
+    }
+    return ship;
+}
+
+//  Battle damage shows once a ship has been lost, and gets worse with each
+//  one after; extra lives don't repair it. Stage 0 is undamaged.
+pub const DAMAGE_STAGES: i32 = 3;
+
+pub fn damage_sprite(ship: Ship, ships_lost: i32) -> Option<&'static sprite::Sprite> {
+    if ships_lost <= 0 {
+        return None;
+    }
+    let stage = ships_lost.min(DAMAGE_STAGES) as usize - 1;
+    return Some(&assets::DAMAGE_SPRITES[ship.hull * DAMAGE_STAGES as usize + stage]);
+}
+
+pub fn hitbox(ship: Ship, hitboxes: &[sprite::Hitbox]) -> sprite::Hitbox {
+    let hb = &hitboxes[sprite(ship).index];
+    let scale = hull(ship).hitbox_scale;
+    return sprite::Hitbox {
+        f_w: hb.f_w * scale,
+        f_h: hb.f_h * scale,
+    };
+}
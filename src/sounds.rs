@@ -1,6 +1,7 @@
 use crate::assets;
 use crate::explosion;
 use crate::state;
+use crate::ufo;
 use macroquad::audio;
 
 fn calc_bassline_index(cnt: usize, ratio: f32, level: i32) -> usize {
@@ -8,7 +9,27 @@ fn calc_bassline_index(cnt: usize, ratio: f32, level: i32) -> usize {
 }
 
 pub fn update_sounds(delta_time: f32, st: &mut state::State, ass: &assets::Assets) {
-    if !st.paused && st.player_state != state::PlayerState::GameOver {
+    //  the UFO warble loops for as long as it's flying
+    let ufo_flying = match &st.ufo {
+        Some(u) => !ufo::is_shown_as_points(u),
+        None => false,
+    };
+    let want_ufo_sound = ufo_flying && !st.paused && !state::is_game_over(st);
+    if want_ufo_sound && !st.ufo_sound_on {
+        audio::play_sound(
+            ass.sounds[assets::IX_SOUND_UFO],
+            audio::PlaySoundParams {
+                looped: true,
+                volume: 0.6,
+            },
+        );
+        st.ufo_sound_on = true;
+    } else if !want_ufo_sound && st.ufo_sound_on {
+        audio::stop_sound(ass.sounds[assets::IX_SOUND_UFO]);
+        st.ufo_sound_on = false;
+    }
+
+    if !st.paused && !state::is_game_over(st) {
         if st.reset_countdown <= 0.0 {
             st.bassline_time -= delta_time;
             if st.bassline_time <= 0.0 {
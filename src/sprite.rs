@@ -3,7 +3,15 @@
 use macroquad::prelude as mq;
 use std::borrow::Cow;
 
+//  Sprites are authored against this screen width; f_w/f_h are fractions of it
+pub const ASSUMED_SCREEN_WIDTH: i32 = 2400;
+
 pub fn draw_sprite(left : f32, top : f32, width : f32, spr: &LoadedSprite, x: f32, y: f32, r: f32, s: f32) {
+    draw_sprite_tinted(left, top, width, spr, x, y, r, s, mq::WHITE);
+}
+
+//  Same as draw_sprite, but the texture is multiplied by color
+pub fn draw_sprite_tinted(left : f32, top : f32, width : f32, spr: &LoadedSprite, x: f32, y: f32, r: f32, s: f32, color: mq::Color) {
     let dtp = mq::DrawTextureParams {
         dest_size: Some(mq::vec2(
             width * spr.f_w * s,
@@ -16,7 +24,7 @@ pub fn draw_sprite(left : f32, top : f32, width : f32, spr: &LoadedSprite, x: f3
         spr.texture,
         left + width * (x - spr.f_w*0.5*s),
         top + width * (y - spr.f_w*0.5*s),
-        mq::WHITE,
+        color,
         dtp,
     );
 }
@@ -35,3 +43,16 @@ pub struct LoadedSprite {
     pub f_w: f32,
     pub f_h: f32,
 }
+
+//  Size of a sprite in playfield units, without needing the texture loaded
+pub struct Hitbox {
+    pub f_w: f32,
+    pub f_h: f32,
+}
+
+pub fn hitbox(spr: &Sprite) -> Hitbox {
+    return Hitbox {
+        f_w: (spr.width_pix as f32) / (ASSUMED_SCREEN_WIDTH as f32) * spr.scale,
+        f_h: (spr.height_pix as f32) / (ASSUMED_SCREEN_WIDTH as f32) * spr.scale,
+    };
+}
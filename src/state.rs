@@ -1,32 +1,59 @@
 //  state.rs
 
 use crate::assets;
+use crate::boss;
+use crate::bunker;
+use crate::dive;
 use crate::explosion;
 use crate::highscore;
+use crate::input;
+use crate::levels;
+use crate::meteor;
 use crate::params;
+use crate::powerup;
+use crate::rng;
+use crate::ship;
 use crate::sprite;
 use crate::telemetry;
+use crate::trig;
+use crate::ufo;
+use crate::versus;
+use crate::wave;
 use macroquad::prelude as mq;
-use macroquad::rand;
+use std::rc::Rc;
 
+#[derive(Clone)]
 pub struct Bullet {
     pub xpos: f32,
     pub ypos: f32,
+    pub last_xpos: f32, //  used for render interpolation
+    pub last_ypos: f32,
     pub velocity: f32,
-    pub fresh: bool, //  used for sound
+    pub dx: f32,       //  sideways drift, for spread shot
+    pub player: usize, //  who fired it, and so who scores
+    pub fresh: bool,   //  used for sound
     pub dead: bool,
 }
 
+#[derive(Clone)]
 pub struct Alien {
     pub sprite: &'static sprite::Sprite,
     pub xpos: f32,
     pub ypos: f32,
+    pub last_xpos: f32, //  used for render interpolation
+    pub last_ypos: f32,
     pub phase: f32,
     pub points: i32,
+    pub column: i32, //  bombs drop from the lowest alien in each column
+    pub hit_points: i32,
+    pub flash_timer: f32,         //  counts down after a hit that didn't kill
+    pub dive: Option<dive::Dive>, //  out of formation, attacking
     pub dead: bool,
 }
 
-#[derive(PartialEq)]
+pub const ALIEN_FLASH_TIME: f32 = 0.15;
+
+#[derive(PartialEq, Clone)]
 pub enum AlienState {
     Right,
     Left,
@@ -34,15 +61,72 @@ pub enum AlienState {
     DownToLeft,
 }
 
+//  The arcade dropped three kinds of bomb; these are modelled on them.
+pub struct BombKind {
+    pub sprite: &'static sprite::Sprite,
+    pub speed: f32,      //  multiplier on the level's bomb_speed
+    pub zigzag: f32,     //  sideways sway, 0 for none
+    pub shootable: bool, //  can a player bullet take it out?
+    pub points: i32,     //  awarded for shooting it down
+}
+
+pub static BOMB_KINDS: [BombKind; 3] = [
+    //  slow zigzag
+    BombKind {
+        sprite: &assets::BOMB_ZIGZAG_SPRITE,
+        speed: 0.75,
+        zigzag: 0.015,
+        shootable: true,
+        points: 0,
+    },
+    //  fast straight -- too quick and thin to hit
+    BombKind {
+        sprite: &assets::BOMB_SPRITE,
+        speed: 1.5,
+        zigzag: 0.0,
+        shootable: false,
+        points: 0,
+    },
+    //  plunger
+    BombKind {
+        sprite: &assets::BOMB_PLUNGER_SPRITE,
+        speed: 1.0,
+        zigzag: 0.0,
+        shootable: true,
+        points: 10,
+    },
+];
+
+const BOMB_ZIGZAG_RATE: f32 = 9.0;
+
+#[derive(Clone)]
 pub struct Bomb {
     pub sprite: &'static sprite::Sprite,
+    pub kind: &'static BombKind,
     pub xpos: f32,
     pub ypos: f32,
+    pub last_xpos: f32, //  used for render interpolation
+    pub last_ypos: f32,
     pub phase: f32,
     pub dead: bool,
 }
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone, Copy)]
+pub enum GameOverReason {
+    OutOfLives, //  bombed one time too many
+    Overrun,    //  an alien rammed the player's ship
+    Landed,     //  an alien reached the ground
+}
+
+pub fn reason_name(reason: GameOverReason) -> &'static str {
+    return match reason {
+        GameOverReason::OutOfLives => "out_of_lives",
+        GameOverReason::Overrun => "overrun",
+        GameOverReason::Landed => "landed",
+    };
+}
+
+#[derive(PartialEq, Clone, Copy)]
 pub enum PlayerState {
     Playing,
     HitExploding,
@@ -50,85 +134,215 @@ pub enum PlayerState {
     GameOver,
 }
 
-const BOMB_MIN_TIME: f32 = 0.7;
-const BOMB_EXTRA_TIME: f32 = 2.5;
 pub const HIT_RESPAWN_TIME: f32 = 0.75;
 const HIT_EXPLODE_TIME: f32 = 0.75;
 const GAME_OVER_TIMEOUT: f32 = 2.0;
 
+//  One ship and whoever is flying it; a co-op game has two. A player who
+//  is out of ships sits in GameOver while the other plays on.
+#[derive(Clone)]
+pub struct Player {
+    pub name: String, //  for the high score table; may be empty
+    pub score: i32,
+    pub lives: i32,
+    pub ships_lost: i32, //  never goes down; drives the damage overlays
+    pub pos_fr: f32,
+    pub last_pos_fr: f32,
+    pub time_to_fire: f32,
+    pub state: PlayerState,
+    pub hit_timer: f32,
+    pub game_over_reason: GameOverReason, //  why this player went out, once they have
+
+    //  the player's choice of ship, and the handling that comes with it
+    pub ship: ship::Ship,
+    pub speed: f32,
+    pub fire_velocity: f32,
+    pub firing_duration: f32, //  scaled by the levels table, see reset_level()
+
+    //  seconds left on each timed power-up
+    pub rapid_fire_time: f32,
+    pub spread_shot_time: f32,
+    pub shield_time: f32,
+
+    //  previous frame's input, for detecting key presses
+    pub last_input: input::Input,
+}
+
+#[derive(Clone)]
 pub struct State {
     pub reset_countdown: f32,
     pub current_level: i32,
     pub paused: bool,
 
-    pub score: i32,
-    pub lives: i32,
-    pub player_pos_fr: f32,
-    pub time_to_fire: f32,
+    pub players: Vec<Player>,
+    pub score: i32, //  everyone's points together
     pub speed_ratio: f32,
-    pub player_state: PlayerState,
-    pub player_hit_timer: f32,
+    pub last_out: usize, //  the player who went out last, see game_over_reason()
 
     pub alien_state: AlienState,
     pub alien_target_y: f32,
     pub time_to_bomb: f32,
+    pub dive_interval: f32, //  0 when this wave doesn't dive
+    pub time_to_dive: f32,
 
     pub bullets: Vec<Bullet>,
     pub aliens: Vec<Alien>,
     pub explosions: Vec<explosion::Explosion>,
     pub bombs: Vec<Bomb>,
+    pub bunkers: Vec<bunker::Bunker>,
+    pub powerups: Vec<powerup::Powerup>,
+    pub ufo: Option<ufo::Ufo>,
+    pub boss: Option<boss::Boss>,
+    pub meteors: Vec<meteor::Meteor>,
+    pub meteor_shower: bool, //  true while a shower runs between levels
+    pub meteors_to_come: i32,
+    pub time_to_meteor: f32,
+    pub time_to_ufo: f32,
+    pub shots_fired: i32,
 
     pub bassline_time: f32,
     pub bassline_speed: f32,
+    pub ufo_sound_on: bool,
 
-    pub player_speed: f32,
-    pub firing_duration: f32,
-    pub fire_velocity: f32,
+    //  set when a ship is lost or the game ends; in a two-player game that
+    //  hands over to the other player, see turns.rs
+    pub turn_over: bool,
+    //  in a versus game, what this side's kills have earned to send to the
+    //  opponent, not yet sent; None in any other game
+    pub outbox: Option<Vec<versus::Attack>>,
+    //  set on a copy that's only run ahead to guess at what's coming, see
+    //  netplay.rs; it reports no telemetry and no high scores
+    pub quiet: bool,
+
+    //  these come from the levels table, see reset_level()
     pub bomb_speed: f32,
+    pub bomb_min_time: f32,
+    pub bomb_extra_time: f32,
+    pub march_speed: f32,
+    pub target_bias: f32,
+
+    //  all gameplay randomness comes from here
+    pub seed: u64,
+    pub rng: rng::Rng,
+
+    //  formations from the wave files, by level; shared, never changed
+    pub waves: Rc<Vec<wave::Wave>>,
 }
 
-pub fn new_game_state() -> State {
+//  Where each ship starts out: the middle for one, spread apart for two
+fn start_pos(ix: usize, count: usize) -> f32 {
+    return 0.48 + (ix as f32 - (count - 1) as f32 * 0.5) * 0.3;
+}
+
+fn new_player(ship: ship::Ship, pos_fr: f32) -> Player {
+    let ship = ship::sanitize(ship);
+    let hull = ship::hull(ship);
+    return Player {
+        name: String::new(),
+        score: 0,
+        lives: 2,
+        ships_lost: 0,
+        pos_fr: pos_fr,
+        last_pos_fr: pos_fr,
+        time_to_fire: 0.0,
+        state: PlayerState::Playing,
+        hit_timer: 0.0,
+        game_over_reason: GameOverReason::OutOfLives,
+
+        ship: ship,
+        speed: hull.player_speed,
+        fire_velocity: hull.fire_velocity,
+        firing_duration: hull.firing_duration,
+
+        rapid_fire_time: 0.0,
+        spread_shot_time: 0.0,
+        shield_time: 0.0,
+
+        last_input: input::Input::default(),
+    };
+}
+
+//  One ship per player; two means co-op, both on screen at once
+pub fn new_game_state(seed: u64, waves: &Rc<Vec<wave::Wave>>, ships: &[ship::Ship]) -> State {
+    let players = ships
+        .iter()
+        .enumerate()
+        .map(|(ix, &ship)| new_player(ship, start_pos(ix, ships.len())))
+        .collect();
     return State {
         reset_countdown: 1.2,
         current_level: 0,
         paused: false,
 
+        players: players,
         score: 0,
-        lives: 2,
-        player_pos_fr: 0.48,
-        time_to_fire: 0.0,
         speed_ratio: 0.1,
-        player_state: PlayerState::Playing,
-        player_hit_timer: 0.0,
+        last_out: 0,
 
         alien_state: AlienState::Right,
         alien_target_y: 0.2,
         time_to_bomb: 1.0,
+        dive_interval: 0.0,
+        time_to_dive: 0.0,
 
         bullets: Vec::new(),
         aliens: Vec::new(),
         explosions: Vec::new(),
         bombs: Vec::new(),
+        bunkers: Vec::new(),
+        powerups: Vec::new(),
+        ufo: None,
+        boss: None,
+        meteors: Vec::new(),
+        meteor_shower: false,
+        meteors_to_come: 0,
+        time_to_meteor: 0.0,
+        time_to_ufo: 20.0,
+        shots_fired: 0,
 
         bassline_time: 0.1,
         bassline_speed: 1.0,
+        ufo_sound_on: false,
+
+        turn_over: false,
+        outbox: None,
+        quiet: false,
 
-        player_speed: 0.3,
-        firing_duration: 0.8,
-        fire_velocity: 1.1,
         bomb_speed: 0.6,
+        bomb_min_time: 0.7,
+        bomb_extra_time: 2.5,
+        march_speed: 1.0,
+        target_bias: 0.1,
+
+        seed: seed,
+        rng: rng::new(seed),
+
+        waves: waves.clone(),
     };
 }
 
-fn push_line(aliens: &mut Vec<Alien>, sprite: &'static sprite::Sprite, ypos: f32, points: i32) {
-    let mut ix = 0;
-    while ix < 10 {
+fn push_line(
+    aliens: &mut Vec<Alien>,
+    sprite: &'static sprite::Sprite,
+    ypos: f32,
+    points: i32,
+    columns: i32,
+    hit_points: i32,
+) {
+    let mut ix: i32 = 0;
+    while ix < columns {
         aliens.push(Alien {
             sprite: sprite,
             xpos: 0.07 + 0.07 * (ix as f32),
             ypos: ypos,
+            last_xpos: 0.07 + 0.07 * (ix as f32),
+            last_ypos: ypos,
             phase: 0.0,
             points: points,
+            column: ix,
+            hit_points: hit_points,
+            flash_timer: 0.0,
+            dive: None,
             dead: false,
         });
         ix += 1;
@@ -136,38 +350,516 @@ fn push_line(aliens: &mut Vec<Alien>, sprite: &'static sprite::Sprite, ypos: f32
 }
 
 pub fn reset_level(state: &mut State) {
-    telemetry::tele_new_level(state.current_level, state.score);
+    if !state.quiet {
+        telemetry::tele_new_level(state.current_level, state.score);
+    }
 
     state.bullets = Vec::new();
     state.aliens = Vec::new();
     state.explosions = Vec::new();
     state.bombs = Vec::new();
-    state.player_pos_fr = 0.48;
+    state.bunkers = bunker::new_row();
+    state.powerups = Vec::new();
+    state.ufo = None;
+    state.boss = None;
+    state.meteors = Vec::new();
+    state.time_to_ufo = ufo::time_to_next(&mut state.rng);
 
     state.bassline_time = 0.1;
     state.bassline_speed = 1.0;
 
-    let mut ypos = 0.2;
-
-    push_line(&mut state.aliens, &assets::ENEMY3_SPRITE, ypos, 30);
-    ypos += 0.1;
-    push_line(&mut state.aliens, &assets::ENEMY2_SPRITE, ypos, 20);
-    ypos += 0.1;
-    push_line(&mut state.aliens, &assets::ENEMY2_SPRITE, ypos, 20);
-    ypos += 0.1;
-    push_line(&mut state.aliens, &assets::ENEMY1_SPRITE, ypos, 10);
-    ypos += 0.1;
-    push_line(&mut state.aliens, &assets::ENEMY1_SPRITE, ypos, 10);
+    let lp = levels::for_level(state.current_level);
+    let count = state.players.len();
+    for (ix, p) in state.players.iter_mut().enumerate() {
+        p.pos_fr = start_pos(ix, count);
+        p.last_pos_fr = p.pos_fr;
+        //  the table's cooldowns are for the original ship; other hulls scale them
+        p.firing_duration = ship::hull(p.ship).firing_duration * lp.firing_duration
+            / levels::for_level(1).firing_duration;
+    }
+    state.bomb_speed = lp.bomb_speed;
+    state.bomb_min_time = lp.bomb_min_time;
+    state.bomb_extra_time = lp.bomb_extra_time;
+    state.march_speed = lp.march_speed;
+    state.target_bias = lp.target_bias;
 
     state.alien_state = AlienState::Right;
-    state.alien_target_y = ypos;
     state.time_to_bomb = 1.0;
+    state.dive_interval = 0.0;
+
+    if boss::is_boss_level(state.current_level) {
+        //  no formation this time
+        state.boss = Some(boss::new(state.current_level));
+        state.alien_target_y = 0.0;
+        if !state.quiet {
+            telemetry::tele_boss_appeared(state.current_level, state.score);
+        }
+        return;
+    }
+
+    let wv = wave::for_level(&state.waves, state.current_level);
+    if let Some(interval) = wv.dive_interval {
+        state.dive_interval = interval;
+        state.time_to_dive = dive::time_to_next(&mut state.rng, interval);
+    }
+    let mut ypos = wv.start_y.unwrap_or(lp.start_y);
+    for (ix, row) in wv.rows.iter().enumerate() {
+        if ix > 0 {
+            ypos += 0.1;
+        }
+        //  parse() vetted the sprite name
+        let spr = wave::sprite_by_name(&row.sprite).unwrap_or(&assets::ENEMY1_SPRITE);
+        push_line(
+            &mut state.aliens,
+            spr,
+            ypos,
+            row.points,
+            row.columns,
+            row.hit_points,
+        );
+    }
+
+    state.alien_target_y = ypos;
+}
+
+//  Played between levels: no aliens, just rocks to shoot and dodge
+fn start_meteor_shower(state: &mut State) {
+    if !state.quiet {
+        telemetry::tele_meteor_shower(state.current_level, state.score);
+    }
+    state.meteor_shower = true;
+    state.meteors_to_come = meteor::SHOWER_METEORS;
+    state.time_to_meteor = 0.5;
+    state.bullets = Vec::new();
+    state.bombs = Vec::new();
+    state.powerups = Vec::new();
+    state.meteors = Vec::new();
+}
+
+fn player_hit(state: &mut State, pix: usize) {
+    let p = &mut state.players[pix];
+    p.state = PlayerState::HitExploding;
+    p.hit_timer = HIT_EXPLODE_TIME;
+    //  getting hit costs whatever the player had collected
+    p.rapid_fire_time = 0.0;
+    p.spread_shot_time = 0.0;
+    p.shield_time = 0.0;
+    state
+        .explosions
+        .push(explosion::new_player(&mut state.rng, p.pos_fr, 1.33 - 0.06));
+}
+
+//  Is any ship still flying, or waiting to respawn?
+pub fn is_game_over(state: &State) -> bool {
+    return state
+        .players
+        .iter()
+        .all(|p| p.state == PlayerState::GameOver);
+}
+
+//  Why the game ended, as far as whoever went out last is concerned
+pub fn game_over_reason(state: &State) -> GameOverReason {
+    return state.players[state.last_out].game_over_reason;
+}
+
+//  Which player the aliens go after next: any one still in the game
+fn pick_target(state: &mut State) -> Option<usize> {
+    let alive: Vec<usize> = (0..state.players.len())
+        .filter(|&ix| state.players[ix].state != PlayerState::GameOver)
+        .collect();
+    if alive.len() <= 1 {
+        //  no need to roll a die; keeps one-player games as they were
+        return alive.first().copied();
+    }
+    return Some(alive[rng::gen_range_usize(&mut state.rng, 0, alive.len())]);
+}
+
+//  Meteors drift down and tumble; bullets chip them and break them up,
+//  and they smash into bunkers and the player. Returns true once the shower
+//  is over.
+fn evolve_meteors(delta_time: f32, state: &mut State, hitboxes: &[sprite::Hitbox]) -> bool {
+    if state.meteors_to_come > 0 {
+        state.time_to_meteor -= delta_time;
+        if state.time_to_meteor <= 0.0 {
+            state.time_to_meteor += meteor::time_to_next(&mut state.rng);
+            state.meteors_to_come -= 1;
+            let m = meteor::new(&mut state.rng);
+            state.meteors.push(m);
+        }
+    }
+
+    let bspr: &sprite::Hitbox = &hitboxes[assets::IX_LASER];
+    let shieldspr: &sprite::Hitbox = &hitboxes[assets::IX_SHIELD_3];
+    let mut pieces: Vec<meteor::Meteor> = Vec::new();
+    let mut players_hit: Vec<(usize, usize)> = Vec::new(); //  (player, meteor)
+    for (ix, m) in state.meteors.iter_mut().enumerate() {
+        m.last_xpos = m.xpos;
+        m.last_ypos = m.ypos;
+        m.last_rotation = m.rotation;
+        meteor::evolve(delta_time, m);
+        //  rocks are round; don't count the corners of the box
+        let mspr: &sprite::Hitbox = &hitboxes[m.sprite.index];
+        let (hw, hh) = (mspr.f_w * 0.4, mspr.f_h * 0.4);
+        for bullet in state.bullets.iter_mut() {
+            if bullet.dead || m.dead {
+                continue;
+            }
+            if bullet.xpos + bspr.f_w * 0.5 > m.xpos - hw
+                && bullet.xpos - bspr.f_w * 0.5 < m.xpos + hw
+                && bullet.ypos < m.ypos + hh
+                && bullet.ypos + bspr.f_h > m.ypos - hh
+            {
+                bullet.dead = true;
+                if meteor::hit(m) {
+                    let points = meteor::SIZES[m.size].points;
+                    state.score += points;
+                    state.players[bullet.player].score += points;
+                    if !state.quiet {
+                        telemetry::tele_meteor_shot(
+                            m.xpos,
+                            state.score,
+                            meteor::SIZES[m.size].name,
+                            points,
+                        );
+                    }
+                    state
+                        .explosions
+                        .push(explosion::new(&mut state.rng, m.xpos, m.ypos));
+                    pieces.extend(meteor::split(&mut state.rng, m));
+                } else {
+                    state.explosions.push(explosion::new_small(
+                        &mut state.rng,
+                        bullet.xpos,
+                        bullet.ypos,
+                    ));
+                }
+            }
+        }
+        if m.dead {
+            continue;
+        }
+        let smashed = state.bunkers.iter_mut().any(|bk| {
+            bunker::hit(
+                bk,
+                m.xpos - hw,
+                m.xpos + hw,
+                m.ypos - hh,
+                m.ypos + hh,
+                false,
+            )
+        });
+        if smashed {
+            m.dead = true;
+            state
+                .explosions
+                .push(explosion::new_small(&mut state.rng, m.xpos, m.ypos));
+            continue;
+        }
+        for (pix, p) in state.players.iter().enumerate() {
+            if p.state != PlayerState::Playing || m.dead {
+                continue;
+            }
+            let playspr = ship::hitbox(p.ship, hitboxes);
+            if p.shield_time > 0.0
+                && m.ypos + hh >= 1.33 - 0.06 - shieldspr.f_h * 0.5
+                && m.xpos + hw >= p.pos_fr - shieldspr.f_w * 0.5
+                && m.xpos - hw <= p.pos_fr + shieldspr.f_w * 0.5
+            {
+                //  the bubble soaks it up
+                m.dead = true;
+                state
+                    .explosions
+                    .push(explosion::new_small(&mut state.rng, m.xpos, m.ypos));
+            } else if m.ypos + hh >= 1.33 - 0.06 - playspr.f_h * 0.5
+                && m.ypos - hh <= 1.33 - 0.06 + playspr.f_h * 0.5
+                && m.xpos + hw >= p.pos_fr - playspr.f_w * 0.45
+                && m.xpos - hw <= p.pos_fr + playspr.f_w * 0.45
+            {
+                m.dead = true;
+                players_hit.push((pix, ix));
+            }
+        }
+    }
+    for (pix, ix) in players_hit {
+        let size = meteor::SIZES[state.meteors[ix].size].name;
+        player_hit(state, pix);
+        let p = &state.players[pix];
+        if !state.quiet {
+            telemetry::tele_meteor_hit(p.pos_fr, state.score, size, p.lives);
+        }
+    }
+    state.bullets.retain(|bullet| !bullet.dead);
+    state.meteors.retain(|m| !m.dead);
+    state.meteors.extend(pieces);
+
+    return state.meteors_to_come == 0 && state.meteors.is_empty();
+}
+
+fn new_bomb(kind: &'static BombKind, xpos: f32, ypos: f32) -> Bomb {
+    return Bomb {
+        sprite: kind.sprite,
+        kind: kind,
+        xpos: xpos,
+        ypos: ypos,
+        last_xpos: xpos,
+        last_ypos: ypos,
+        phase: 0.0,
+        dead: false,
+    };
+}
+
+fn new_bullet(player: usize, xpos: f32, velocity: f32, dx: f32) -> Bullet {
+    return Bullet {
+        xpos: xpos,
+        ypos: 1.33 * 0.94 - 0.01,
+        last_xpos: xpos,
+        last_ypos: 1.33 * 0.94 - 0.01,
+        velocity: velocity,
+        dx: dx,
+        player: player,
+        dead: false,
+        fresh: true,
+    };
+}
+
+//  Power-ups go to whoever caught them
+fn apply_powerup(state: &mut State, pix: usize, kind: &'static powerup::PowerupKind) {
+    let p = &mut state.players[pix];
+    match kind.effect {
+        powerup::Effect::RapidFire => p.rapid_fire_time = kind.duration,
+        powerup::Effect::SpreadShot => p.spread_shot_time = kind.duration,
+        powerup::Effect::Shield => p.shield_time = kind.duration,
+        powerup::Effect::ExtraLife => {
+            if p.lives < powerup::MAX_LIVES {
+                p.lives += 1;
+            }
+        }
+    }
+    if !state.quiet {
+        telemetry::tele_powerup(p.pos_fr, state.score, kind.name);
+    }
+}
+
+//  This player is out; the game is over once everyone is
+fn player_out(state: &mut State, pix: usize, reason: GameOverReason, remaining: usize) {
+    let p = &mut state.players[pix];
+    p.state = PlayerState::GameOver;
+    p.hit_timer = GAME_OVER_TIMEOUT;
+    p.game_over_reason = reason;
+    state.last_out = pix;
+    if !state.quiet {
+        telemetry::tele_game_over(
+            p.pos_fr,
+            state.score,
+            remaining,
+            state.current_level,
+            reason_name(reason),
+        );
+    }
+    if !state.quiet {
+        highscore::register(&p.name, p.score);
+    }
+    state.turn_over = true;
+}
+
+//  Bombs only drop from the lowest alien in each column; the rest would be
+//  bombing their friends. Some of the time (more, at higher levels) the
+//  aliens pick the column closest to the player instead of a random one.
+fn pick_bomber(state: &mut State) -> Option<usize> {
+    let mut bottoms: Vec<usize> = Vec::new();
+    for (ix, alien) in state.aliens.iter().enumerate() {
+        //  divers fire for themselves
+        if alien.dead || alien.dive.is_some() {
+            continue;
+        }
+        match bottoms
+            .iter()
+            .position(|&b| state.aliens[b].column == alien.column)
+        {
+            Some(p) => {
+                if alien.ypos > state.aliens[bottoms[p]].ypos {
+                    bottoms[p] = ix;
+                }
+            }
+            None => bottoms.push(ix),
+        }
+    }
+    if bottoms.is_empty() {
+        return None;
+    }
+    if rng::gen_range_f32(&mut state.rng, 0.0, 1.0) < state.target_bias {
+        if let Some(target) = pick_target(state) {
+            let target_x = state.players[target].pos_fr;
+            let mut best = bottoms[0];
+            for &b in bottoms.iter() {
+                if (state.aliens[b].xpos - target_x).abs()
+                    < (state.aliens[best].xpos - target_x).abs()
+                {
+                    best = b;
+                }
+            }
+            return Some(best);
+        }
+    }
+    return Some(bottoms[rng::gen_range_usize(&mut state.rng, 0, bottoms.len())]);
+}
+
+//  Positions as of the start of a tick, so rendering can blend between ticks
+fn remember_positions(state: &mut State) {
+    for p in state.players.iter_mut() {
+        p.last_pos_fr = p.pos_fr;
+    }
+    for bullet in state.bullets.iter_mut() {
+        bullet.last_xpos = bullet.xpos;
+        bullet.last_ypos = bullet.ypos;
+    }
+    for p in state.powerups.iter_mut() {
+        p.last_ypos = p.ypos;
+    }
+    for alien in state.aliens.iter_mut() {
+        alien.last_xpos = alien.xpos;
+        alien.last_ypos = alien.ypos;
+    }
+    for bomb in state.bombs.iter_mut() {
+        bomb.last_xpos = bomb.xpos;
+        bomb.last_ypos = bomb.ypos;
+    }
+    if let Some(u) = &mut state.ufo {
+        u.last_xpos = u.xpos;
+    }
+    if let Some(b) = &mut state.boss {
+        b.last_xpos = b.xpos;
+        b.last_ypos = b.ypos;
+    }
+}
+
+//  Something a versus opponent sent over. An extra alien joins the top of
+//  the formation; with no formation to join, it comes as a bomb instead.
+pub fn receive_attack(state: &mut State, attack: versus::Attack) {
+    if is_game_over(state) {
+        return;
+    }
+    let in_formation: Vec<&Alien> = state.aliens.iter().filter(|a| a.dive.is_none()).collect();
+    if attack == versus::Attack::Alien && state.reset_countdown <= 0.0 && !in_formation.is_empty() {
+        let min_x = in_formation.iter().fold(1.0, |m: f32, a| m.min(a.xpos));
+        let max_x = in_formation.iter().fold(0.0, |m: f32, a| m.max(a.xpos));
+        let top = in_formation.iter().fold(2.0, |m: f32, a| m.min(a.ypos));
+        let column = state.aliens.iter().map(|a| a.column).max().unwrap_or(0) + 1;
+        let xpos = rng::gen_range_f32(&mut state.rng, min_x, max_x.max(min_x + 0.01));
+        let ypos = (top - 0.1).max(0.12);
+        state.aliens.push(Alien {
+            sprite: &assets::ENEMY1_SPRITE,
+            xpos: xpos,
+            ypos: ypos,
+            last_xpos: xpos,
+            last_ypos: ypos,
+            phase: 0.0,
+            points: 10,
+            column: column,
+            hit_points: 1,
+            flash_timer: 0.0,
+            dive: None,
+            dead: false,
+        });
+        return;
+    }
+    let xpos = rng::gen_range_f32(&mut state.rng, params::LEFT_MARGIN, params::RIGHT_MARGIN);
+    state.bombs.push(new_bomb(&BOMB_KINDS[0], xpos, 0.1));
+}
+
+//  Movement and firing, and getting back into the game after a hit
+fn evolve_player(
+    delta_time: f32,
+    state: &mut State,
+    pix: usize,
+    input: &input::Input,
+    num_aliens: usize,
+) {
+    let count = state.players.len();
+    let p = &mut state.players[pix];
+    if p.state == PlayerState::Playing {
+        //  evolve inputs
+        if input.right {
+            p.pos_fr += delta_time * p.speed;
+        }
+        if input.left {
+            p.pos_fr -= delta_time * p.speed;
+        }
+
+        p.pos_fr = mq::clamp(p.pos_fr, params::LEFT_MARGIN, params::RIGHT_MARGIN);
+        if input.fire {
+            if p.time_to_fire <= 0.0 {
+                //  Note: original Space Invaders only allowed one bullet alive at once
+                p.time_to_fire = if p.rapid_fire_time > 0.0 {
+                    p.firing_duration * powerup::RAPID_FIRE_FACTOR
+                } else {
+                    p.firing_duration
+                };
+                state.shots_fired += 1;
+                state
+                    .bullets
+                    .push(new_bullet(pix, p.pos_fr, p.fire_velocity, 0.0));
+                if p.spread_shot_time > 0.0 {
+                    for dx in [-powerup::SPREAD_VELOCITY, powerup::SPREAD_VELOCITY] {
+                        let mut b = new_bullet(pix, p.pos_fr, p.fire_velocity, dx);
+                        b.fresh = false; //  one laser sound is plenty
+                        state.bullets.push(b);
+                    }
+                }
+                if !state.quiet {
+                    telemetry::tele_shot(p.pos_fr, state.score, state.alien_target_y);
+                }
+            }
+        }
+    } else if p.state == PlayerState::HitExploding {
+        p.hit_timer -= delta_time;
+        if p.hit_timer <= 0.0 {
+            if p.lives > 0 {
+                p.lives -= 1;
+                p.ships_lost += 1;
+                p.state = PlayerState::HitRespawning;
+                p.pos_fr = start_pos(pix, count);
+                p.last_pos_fr = p.pos_fr;
+                p.hit_timer += HIT_RESPAWN_TIME;
+                state.turn_over = true;
+            } else {
+                //  an overrun already recorded its own reason
+                let reason = state.players[pix].game_over_reason;
+                player_out(state, pix, reason, num_aliens);
+            }
+        }
+    } else if p.state == PlayerState::HitRespawning {
+        p.hit_timer -= delta_time;
+        if p.hit_timer <= 0.0 {
+            p.hit_timer = 0.0;
+            p.state = PlayerState::Playing;
+        }
+    } else if p.state == PlayerState::GameOver {
+        p.hit_timer -= delta_time;
+    }
 }
 
-pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets) {
-    if mq::is_key_pressed(mq::KeyCode::Escape) {
+//  Advance the game by delta_time. This touches neither the window nor loaded
+//  textures, so it runs headless given an input stream and a hitbox table.
+pub fn update_state(
+    delta_time: f32,
+    state: &mut State,
+    inputs: &[input::Input],
+    hitboxes: &[sprite::Hitbox],
+) {
+    remember_positions(state);
+
+    //  either player can pause
+    let pause_pressed = state
+        .players
+        .iter()
+        .zip(inputs.iter())
+        .any(|(p, input)| input.pause && !p.last_input.pause);
+    if pause_pressed {
         state.paused = !state.paused;
-        telemetry::tele_pause(state.paused);
+        if !state.quiet {
+            telemetry::tele_pause(state.paused);
+        }
     }
 
     let num_aliens_i = state.aliens.len();
@@ -175,8 +867,10 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
 
     if !state.paused {
         //  evolve timers
-        if state.time_to_fire > 0.0 {
-            state.time_to_fire -= delta_time;
+        for p in state.players.iter_mut() {
+            if p.time_to_fire > 0.0 {
+                p.time_to_fire -= delta_time;
+            }
         }
 
         let mut evolving = false;
@@ -187,84 +881,52 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
         if state.reset_countdown > 0.0 {
             state.reset_countdown -= delta_time;
             if state.reset_countdown <= 0.0 {
-                state.current_level += 1;
-                reset_level(state);
+                if !state.meteor_shower && meteor::shower_after(state.current_level) {
+                    start_meteor_shower(state);
+                } else {
+                    state.meteor_shower = false;
+                    state.current_level += 1;
+                    reset_level(state);
+                }
             }
         } else {
             evolving = true;
 
-            if state.player_state == PlayerState::Playing {
-                //  evolve inputs
-                if mq::is_key_down(mq::KeyCode::Right) || mq::is_key_down(mq::KeyCode::D) {
-                    state.player_pos_fr += delta_time * state.player_speed;
-                }
-                if mq::is_key_down(mq::KeyCode::Left) || mq::is_key_down(mq::KeyCode::A) {
-                    state.player_pos_fr -= delta_time * state.player_speed;
-                }
-
-                state.player_pos_fr = mq::clamp(
-                    state.player_pos_fr,
-                    params::LEFT_MARGIN,
-                    params::RIGHT_MARGIN,
-                );
-                if mq::is_key_down(mq::KeyCode::Space) {
-                    if state.time_to_fire <= 0.0 {
-                        //  Note: original Space Invaders only allowed one bullet alive at once
-                        state.time_to_fire = state.firing_duration;
-                        state.bullets.push(Bullet {
-                            xpos: state.player_pos_fr,
-                            ypos: 1.33 * 0.94 - 0.01,
-                            velocity: state.fire_velocity,
-                            dead: false,
-                            fresh: true,
-                        });
-                        telemetry::tele_shot(
-                            state.player_pos_fr,
-                            state.score,
-                            state.alien_target_y,
-                        );
-                    }
-                }
-            } else if state.player_state == PlayerState::HitExploding {
-                state.player_hit_timer -= delta_time;
-                if state.player_hit_timer <= 0.0 {
-                    if state.lives > 0 {
-                        state.lives -= 1;
-                        state.player_state = PlayerState::HitRespawning;
-                        state.player_pos_fr = 0.48;
-                        state.player_hit_timer += HIT_RESPAWN_TIME;
-                    } else {
-                        state.player_state = PlayerState::GameOver;
-                        evolving = false;
-                        state.player_hit_timer = GAME_OVER_TIMEOUT;
-                        telemetry::tele_game_over(
-                            state.player_pos_fr,
-                            state.score,
-                            num_aliens_i,
-                            state.current_level,
-                        );
-                        highscore::register(state.score);
-                    }
-                }
-            } else if state.player_state == PlayerState::HitRespawning {
-                state.player_hit_timer -= delta_time;
-                if state.player_hit_timer <= 0.0 {
-                    state.player_hit_timer = 0.0;
-                    state.player_state = PlayerState::Playing;
-                }
-            } else if state.player_state == PlayerState::GameOver {
-                state.player_hit_timer -= delta_time;
+            for pix in 0..state.players.len() {
+                let input = inputs.get(pix).copied().unwrap_or_default();
+                evolve_player(delta_time, state, pix, &input, num_aliens_i);
+            }
+            if is_game_over(state) {
                 evolving = false;
             }
 
             //  evolve bullets
+            let lspr: &sprite::Hitbox = &hitboxes[assets::IX_LASER];
             for bullet in state.bullets.iter_mut() {
                 bullet.ypos -= delta_time * bullet.velocity;
-                if bullet.ypos < 0.0 {
+                bullet.xpos += delta_time * bullet.dx;
+                if bullet.ypos < 0.0 || bullet.xpos < 0.0 || bullet.xpos > 1.0 {
                     bullet.dead = true;
                     hasdeadbullet = true;
-                    telemetry::tele_miss(bullet.xpos, state.score, num_aliens_i);
+                    if !state.quiet {
+                        telemetry::tele_miss(bullet.xpos, state.score, num_aliens_i);
+                    }
                     //  Note: original Space Invaders exploded the bullet at the top of the screen
+                } else {
+                    for bk in state.bunkers.iter_mut() {
+                        if bunker::hit(
+                            bk,
+                            bullet.xpos - lspr.f_w * 0.5,
+                            bullet.xpos + lspr.f_w * 0.5,
+                            bullet.ypos,
+                            bullet.ypos + lspr.f_h,
+                            true,
+                        ) {
+                            bullet.dead = true;
+                            hasdeadbullet = true;
+                            break;
+                        }
+                    }
                 }
             }
 
@@ -275,9 +937,8 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
                 let mut min_x = 1.0;
                 let mut max_x = 0.0;
                 let mut max_y = -1.0;
-                let level_speed = (state.current_level as f32 + 4.0) / 5.0;
-                let h_mul: f32 = params::HORIZ_SPEED / (3.0 + num_aliens) * level_speed;
-                let v_mul: f32 = params::VERT_SPEED / (3.0 + num_aliens) * level_speed;
+                let h_mul: f32 = params::HORIZ_SPEED / (3.0 + num_aliens) * state.march_speed;
+                let v_mul: f32 = params::VERT_SPEED / (3.0 + num_aliens) * state.march_speed;
 
                 if state.alien_state == AlienState::Right {
                     alien_dx = h_mul * delta_time;
@@ -294,20 +955,29 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
                     if alien.phase >= 3.1416 {
                         alien.phase -= 6.2832;
                     }
-                    alien.xpos += alien_dx;
-                    if alien.xpos > max_x {
-                        max_x = alien.xpos;
+                    if alien.flash_timer > 0.0 {
+                        alien.flash_timer -= delta_time;
                     }
-                    if alien.xpos < min_x {
-                        min_x = alien.xpos;
+                    //  a diver's slot still marches with the formation
+                    let (slot_x, slot_y) = match &mut alien.dive {
+                        Some(d) => {
+                            dive::shift_slot(d, alien_dx, alien_dy);
+                            (d.slot_x, d.slot_y)
+                        }
+                        None => {
+                            alien.xpos += alien_dx;
+                            alien.ypos += alien_dy;
+                            (alien.xpos, alien.ypos)
+                        }
+                    };
+                    if slot_x > max_x {
+                        max_x = slot_x;
                     }
-                    alien.ypos += alien_dy;
-                    if alien.ypos > max_y {
-                        max_y = alien.ypos;
+                    if slot_x < min_x {
+                        min_x = slot_x;
                     }
-                    if alien.ypos > 1.333 {
-                        alien.dead = true;
-                        hasdeadalien = true;
+                    if slot_y > max_y {
+                        max_y = slot_y;
                     }
                 }
                 let mut adjust_dx = 0.0;
@@ -317,13 +987,17 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
                     state.alien_target_y = max_y + params::DOWN_DISTANCE;
                     adjust_dx = params::RIGHT_MARGIN - max_x; // negative
                     adjust_dy = -adjust_dx * params::VERT_SPEED / params::HORIZ_SPEED;
-                    telemetry::tele_advance(state.alien_target_y, num_aliens_i);
+                    if !state.quiet {
+                        telemetry::tele_advance(state.alien_target_y, num_aliens_i);
+                    }
                 } else if state.alien_state == AlienState::Left && min_x <= params::LEFT_MARGIN {
                     state.alien_state = AlienState::DownToRight;
                     state.alien_target_y = max_y + params::DOWN_DISTANCE;
                     adjust_dx = params::LEFT_MARGIN - min_x; // positive
                     adjust_dy = adjust_dx * params::VERT_SPEED / params::HORIZ_SPEED;
-                    telemetry::tele_advance(state.alien_target_y, num_aliens_i);
+                    if !state.quiet {
+                        telemetry::tele_advance(state.alien_target_y, num_aliens_i);
+                    }
                 } else if state.alien_state == AlienState::DownToRight
                     && max_y >= state.alien_target_y
                 {
@@ -340,20 +1014,185 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
                 //  adjust for fractional movement
                 if adjust_dx != 0.0 || adjust_dy != 0.0 {
                     for alien in state.aliens.iter_mut() {
-                        alien.xpos += adjust_dx;
-                        alien.ypos += adjust_dy;
+                        match &mut alien.dive {
+                            Some(d) => dive::shift_slot(d, adjust_dx, adjust_dy),
+                            None => {
+                                alien.xpos += adjust_dx;
+                                alien.ypos += adjust_dy;
+                            }
+                        }
                     }
                 }
 
+                //  evolve divers, and send out new ones
+                let mut dive_drops: Vec<(f32, f32)> = Vec::new();
+                for alien in state.aliens.iter_mut() {
+                    if let Some(d) = &mut alien.dive {
+                        if dive::evolve(delta_time, d, &mut state.rng) {
+                            dive_drops.push((alien.xpos, alien.ypos));
+                        }
+                        let (x, y) = dive::position(d);
+                        alien.xpos = x;
+                        alien.ypos = y;
+                        if d.done {
+                            alien.dive = None;
+                        }
+                    }
+                }
+                let anyone_playing = state
+                    .players
+                    .iter()
+                    .any(|p| p.state == PlayerState::Playing);
+                if state.dive_interval > 0.0 && anyone_playing {
+                    state.time_to_dive -= delta_time;
+                    if state.time_to_dive <= 0.0 {
+                        state.time_to_dive =
+                            dive::time_to_next(&mut state.rng, state.dive_interval);
+                        let divers = state.aliens.iter().filter(|a| a.dive.is_some()).count();
+                        let idle: Vec<usize> = (0..state.aliens.len())
+                            .filter(|&ix| state.aliens[ix].dive.is_none())
+                            .collect();
+                        let target = pick_target(state);
+                        if let (true, Some(target)) =
+                            (divers < dive::MAX_DIVERS && !idle.is_empty(), target)
+                        {
+                            let ix = idle[rng::gen_range_usize(&mut state.rng, 0, idle.len())];
+                            let alien = &mut state.aliens[ix];
+                            alien.dive = Some(dive::new(
+                                &mut state.rng,
+                                alien.xpos,
+                                alien.ypos,
+                                state.players[target].pos_fr,
+                            ));
+                        }
+                    }
+                }
+                for (xpos, ypos) in dive_drops {
+                    //  divers fire the fast straight kind
+                    state.bombs.push(new_bomb(&BOMB_KINDS[1], xpos, ypos));
+                }
+
                 state.time_to_bomb -= delta_time;
 
+                //  evolve the mystery UFO
+                if state.ufo.is_none() {
+                    state.time_to_ufo -= delta_time;
+                    if state.time_to_ufo <= 0.0 {
+                        state.time_to_ufo = ufo::time_to_next(&mut state.rng);
+                        if num_aliens_i >= ufo::UFO_MIN_ALIENS {
+                            state.ufo = Some(ufo::new(&mut state.rng, state.shots_fired));
+                        }
+                    }
+                }
+                let bspr: &sprite::Hitbox = &hitboxes[assets::IX_LASER];
+                if let Some(u) = &mut state.ufo {
+                    if ufo::evolve(delta_time, u) {
+                        if !state.quiet {
+                            telemetry::tele_ufo_escaped(state.score, state.shots_fired);
+                        }
+                    } else if !ufo::is_shown_as_points(u) {
+                        let uspr: &sprite::Hitbox = &hitboxes[u.sprite.index];
+                        for bullet in state.bullets.iter_mut() {
+                            if bullet.dead {
+                                continue;
+                            }
+                            if bullet.xpos + bspr.f_w * 0.5 > u.xpos - uspr.f_w * 0.5
+                                && bullet.xpos - bspr.f_w * 0.5 < u.xpos + uspr.f_w * 0.5
+                                && bullet.ypos > ufo::UFO_Y - uspr.f_h * 0.5
+                                && bullet.ypos - bspr.f_w < ufo::UFO_Y + uspr.f_h * 0.5
+                            {
+                                let points = ufo::points_for_shot(state.shots_fired);
+                                state.score += points;
+                                state.players[bullet.player].score += points;
+                                bullet.dead = true;
+                                hasdeadbullet = true;
+                                ufo::hit(u, points);
+                                if !state.quiet {
+                                    telemetry::tele_ufo_hit(
+                                        u.xpos,
+                                        state.score,
+                                        points,
+                                        state.shots_fired,
+                                    );
+                                }
+                                state.explosions.push(explosion::new_player(
+                                    &mut state.rng,
+                                    u.xpos,
+                                    ufo::UFO_Y,
+                                ));
+                                break;
+                            }
+                        }
+                    }
+                    if u.dead {
+                        state.ufo = None;
+                    }
+                }
+
+                //  evolve the boss
+                let mut boss_drops: Vec<(f32, f32)> = Vec::new();
+                if let Some(b) = &mut state.boss {
+                    boss_drops = boss::evolve(delta_time, b, &mut state.rng, &mut state.explosions);
+                    if !boss::is_dying(b) {
+                        let bossspr: &sprite::Hitbox = &hitboxes[b.sprite.index];
+                        for bullet in state.bullets.iter_mut() {
+                            if bullet.dead {
+                                continue;
+                            }
+                            if bullet.xpos + bspr.f_w * 0.5 > b.xpos - bossspr.f_w * 0.5
+                                && bullet.xpos - bspr.f_w * 0.5 < b.xpos + bossspr.f_w * 0.5
+                                && bullet.ypos > b.ypos - bossspr.f_h * 0.5
+                                && bullet.ypos - bspr.f_w < b.ypos + bossspr.f_h * 0.5
+                            {
+                                bullet.dead = true;
+                                hasdeadbullet = true;
+                                state.explosions.push(explosion::new_small(
+                                    &mut state.rng,
+                                    bullet.xpos,
+                                    bullet.ypos,
+                                ));
+                                if boss::hit(b) {
+                                    state.score += b.points;
+                                    state.players[bullet.player].score += b.points;
+                                    if !state.quiet {
+                                        telemetry::tele_boss_defeated(
+                                            b.xpos,
+                                            state.score,
+                                            b.points,
+                                            state.current_level,
+                                        );
+                                    }
+                                    break;
+                                }
+                            }
+                        }
+                    }
+                    if b.dead {
+                        state.boss = None;
+                        //  wave clear!
+                        state.reset_countdown = 1.2;
+                    }
+                }
+                for (xpos, ypos) in boss_drops {
+                    let kind =
+                        &BOMB_KINDS[rng::gen_range_usize(&mut state.rng, 0, BOMB_KINDS.len())];
+                    state.bombs.push(new_bomb(kind, xpos, ypos));
+                }
+
+                if state.meteor_shower && evolve_meteors(delta_time, state, hitboxes) {
+                    //  the shower has passed
+                    state.reset_countdown = 1.2;
+                }
+
                 //  detect alien collisions with things
-                let bspr: &sprite::LoadedSprite = &assets.sprites[assets::IX_LASER];
+                let mut overrun: Vec<usize> = Vec::new();
+                let mut landed = false;
+                let mut rammed: Vec<usize> = Vec::new();
                 for alien in state.aliens.iter_mut() {
                     if alien.dead {
                         continue;
                     }
-                    let aspr: &sprite::LoadedSprite = &assets.sprites[alien.sprite.index];
+                    let aspr: &sprite::Hitbox = &hitboxes[alien.sprite.index];
                     for bullet in state.bullets.iter_mut() {
                         if bullet.dead {
                             continue;
@@ -363,78 +1202,303 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
                             && bullet.ypos > alien.ypos - aspr.f_h * 0.5
                             && bullet.ypos - bspr.f_w < alien.ypos + aspr.f_h * 0.5
                         {
-                            state.score += alien.points;
                             bullet.dead = true;
-                            alien.dead = true;
                             hasdeadbullet = true;
+                            alien.hit_points -= 1;
+                            if alien.hit_points > 0 {
+                                //  armored; no points until it's destroyed
+                                alien.flash_timer = ALIEN_FLASH_TIME;
+                                if !state.quiet {
+                                    telemetry::tele_damage(
+                                        bullet.xpos,
+                                        state.score,
+                                        alien.sprite.index,
+                                        alien.hit_points,
+                                        num_aliens_i,
+                                    );
+                                }
+                                state.explosions.push(explosion::new_small(
+                                    &mut state.rng,
+                                    bullet.xpos,
+                                    bullet.ypos,
+                                ));
+                                break;
+                            }
+                            state.score += alien.points;
+                            state.players[bullet.player].score += alien.points;
+                            alien.dead = true;
                             hasdeadalien = true;
-                            telemetry::tele_hit(
+                            if let Some(out) = &mut state.outbox {
+                                out.push(versus::attack_for_kill(alien.points));
+                            }
+                            if !state.quiet {
+                                telemetry::tele_hit(
+                                    bullet.xpos,
+                                    state.score,
+                                    alien.sprite.index,
+                                    alien.points,
+                                    num_aliens_i - 1,
+                                );
+                            }
+                            state.explosions.push(explosion::new(
+                                &mut state.rng,
                                 bullet.xpos,
+                                bullet.ypos,
+                            ));
+                            if let Some(p) =
+                                powerup::maybe_drop(&mut state.rng, alien.xpos, alien.ypos)
+                            {
+                                state.powerups.push(p);
+                            }
+                            break;
+                        }
+                    }
+                    for bk in state.bunkers.iter_mut() {
+                        bunker::erase(
+                            bk,
+                            alien.xpos - aspr.f_w * 0.5,
+                            alien.xpos + aspr.f_w * 0.5,
+                            alien.ypos - aspr.f_h * 0.5,
+                            alien.ypos + aspr.f_h * 0.5,
+                        );
+                    }
+                    if alien.ypos + aspr.f_h * 0.5 >= 1.333 && alien.dive.is_none() {
+                        landed = true;
+                    }
+                    for (pix, p) in state.players.iter().enumerate() {
+                        if p.state != PlayerState::Playing {
+                            continue;
+                        }
+                        let playspr = ship::hitbox(p.ship, hitboxes);
+                        if alien.dive.is_some() {
+                            //  a diver that hits the player takes itself out too
+                            if !alien.dead
+                                && alien.ypos + aspr.f_h * 0.5 >= 1.333 - 0.06 - playspr.f_h * 0.5
+                                && alien.ypos - aspr.f_h * 0.5 <= 1.333 - 0.06 + playspr.f_h * 0.5
+                                && alien.xpos + aspr.f_w * 0.5 >= p.pos_fr - playspr.f_w * 0.45
+                                && alien.xpos - aspr.f_w * 0.5 <= p.pos_fr + playspr.f_w * 0.45
+                            {
+                                alien.dead = true;
+                                hasdeadalien = true;
+                                rammed.push(pix);
+                                state.explosions.push(explosion::new(
+                                    &mut state.rng,
+                                    alien.xpos,
+                                    alien.ypos,
+                                ));
+                            }
+                        } else if alien.ypos + aspr.f_h * 0.5 >= 1.333 - 0.06 - playspr.f_h * 0.5
+                            && alien.xpos + aspr.f_w * 0.5 >= p.pos_fr - playspr.f_w * 0.45
+                            && alien.xpos - aspr.f_w * 0.5 <= p.pos_fr + playspr.f_w * 0.45
+                            && !overrun.contains(&pix)
+                        {
+                            overrun.push(pix);
+                        }
+                    }
+                } //  endfor aliens
+
+                if landed {
+                    //  invasion complete -- no amount of spare ships helps
+                    for pix in 0..state.players.len() {
+                        if state.players[pix].state == PlayerState::GameOver {
+                            continue;
+                        }
+                        let xpos = state.players[pix].pos_fr;
+                        state.explosions.push(explosion::new_player(
+                            &mut state.rng,
+                            xpos,
+                            1.33 - 0.06,
+                        ));
+                        player_out(state, pix, GameOverReason::Landed, num_aliens_i);
+                    }
+                } else {
+                    for pix in overrun {
+                        let p = &mut state.players[pix];
+                        p.state = PlayerState::HitExploding;
+                        p.hit_timer = HIT_EXPLODE_TIME;
+                        p.lives = 0;
+                        p.game_over_reason = GameOverReason::Overrun;
+                        state.explosions.push(explosion::new_player(
+                            &mut state.rng,
+                            p.pos_fr,
+                            1.33 - 0.06,
+                        ));
+                    }
+                    for pix in rammed {
+                        if state.players[pix].state != PlayerState::Playing {
+                            continue;
+                        }
+                        player_hit(state, pix);
+                        let p = &state.players[pix];
+                        if !state.quiet {
+                            telemetry::tele_rammed(
+                                p.pos_fr,
                                 state.score,
-                                alien.sprite.index,
-                                alien.points,
                                 num_aliens_i - 1,
+                                p.lives,
                             );
-                            state
-                                .explosions
-                                .push(explosion::new(bullet.xpos, bullet.ypos));
                         }
                     }
-                    //  TODO: check barrier collision
-                    //  TODO: check player collision
-                } //  endfor aliens
+                }
 
                 if state.time_to_bomb <= 0.0 {
-                    state.time_to_bomb += rand::gen_range(
-                        BOMB_MIN_TIME,
-                        BOMB_MIN_TIME
-                            + BOMB_EXTRA_TIME * (4.0 / (3.0 + state.current_level as f32)),
+                    state.time_to_bomb += rng::gen_range_f32(
+                        &mut state.rng,
+                        state.bomb_min_time,
+                        state.bomb_min_time + state.bomb_extra_time,
                     );
-                    let source = &state.aliens[rand::gen_range(0, num_aliens_i)];
-                    state.bombs.push(Bomb {
-                        sprite: &assets::BOMB_SPRITE,
-                        xpos: source.xpos,
-                        ypos: source.ypos,
-                        phase: 0.0,
-                        dead: false,
-                    })
+                    if let Some(six) = pick_bomber(state) {
+                        let (xpos, ypos) = (state.aliens[six].xpos, state.aliens[six].ypos);
+                        let kind =
+                            &BOMB_KINDS[rng::gen_range_usize(&mut state.rng, 0, BOMB_KINDS.len())];
+                        state.bombs.push(new_bomb(kind, xpos, ypos));
+                    }
                 }
 
-                let playspr: &sprite::LoadedSprite = &assets.sprites[assets::IX_PLAYER];
+                let shieldspr: &sprite::Hitbox = &hitboxes[assets::IX_SHIELD_3];
+                let mut bombed: Vec<usize> = Vec::new();
                 for bomb in state.bombs.iter_mut() {
                     if bomb.dead {
                         continue;
                     }
-                    bomb.ypos += state.bomb_speed * delta_time;
-                    if bomb.ypos > 1.33 {
-                        bomb.dead = true;
-                        hasdeadbomb = true;
-                    } else if state.player_state == PlayerState::Playing
-                        && bomb.ypos >= 1.33 - 0.06 - playspr.f_h * 0.5
-                        && bomb.ypos < 1.33 - 0.06 + playspr.f_h * 0.5
-                        && bomb.xpos >= state.player_pos_fr - playspr.f_w * 0.45
-                        && bomb.xpos <= state.player_pos_fr + playspr.f_w * 0.45
-                    {
+                    bomb.ypos += state.bomb_speed * bomb.kind.speed * delta_time;
+                    if bomb.kind.zigzag > 0.0 {
+                        bomb.phase += BOMB_ZIGZAG_RATE * delta_time;
+                        bomb.xpos += trig::cos(bomb.phase)
+                            * bomb.kind.zigzag
+                            * BOMB_ZIGZAG_RATE
+                            * delta_time;
+                    }
+                    let bombspr: &sprite::Hitbox = &hitboxes[bomb.sprite.index];
+                    if bomb.kind.shootable {
+                        for bullet in state.bullets.iter_mut() {
+                            if bullet.dead {
+                                continue;
+                            }
+                            if bullet.xpos + bspr.f_w * 0.5 > bomb.xpos - bombspr.f_w * 0.5
+                                && bullet.xpos - bspr.f_w * 0.5 < bomb.xpos + bombspr.f_w * 0.5
+                                && bullet.ypos < bomb.ypos + bombspr.f_h * 0.5
+                                && bullet.ypos + bspr.f_h > bomb.ypos - bombspr.f_h * 0.5
+                            {
+                                bullet.dead = true;
+                                hasdeadbullet = true;
+                                bomb.dead = true;
+                                hasdeadbomb = true;
+                                state.score += bomb.kind.points;
+                                state.players[bullet.player].score += bomb.kind.points;
+                                if !state.quiet {
+                                    telemetry::tele_bomb_shot(
+                                        bomb.xpos,
+                                        state.score,
+                                        bomb.sprite.index,
+                                        bomb.kind.points,
+                                    );
+                                }
+                                state.explosions.push(explosion::new_small(
+                                    &mut state.rng,
+                                    bomb.xpos,
+                                    bomb.ypos,
+                                ));
+                                break;
+                            }
+                        }
+                        if bomb.dead {
+                            continue;
+                        }
+                    }
+                    let blocked = state.bunkers.iter_mut().any(|bk| {
+                        bunker::hit(
+                            bk,
+                            bomb.xpos - bombspr.f_w * 0.5,
+                            bomb.xpos + bombspr.f_w * 0.5,
+                            bomb.ypos - bombspr.f_h * 0.5,
+                            bomb.ypos + bombspr.f_h * 0.5,
+                            false,
+                        )
+                    });
+                    if blocked || bomb.ypos > 1.33 {
                         bomb.dead = true;
                         hasdeadbomb = true;
-                        state.player_state = PlayerState::HitExploding;
-                        state.player_hit_timer = HIT_EXPLODE_TIME;
-                        state
-                            .explosions
-                            .push(explosion::new_player(state.player_pos_fr, 1.33 - 0.06));
-                        telemetry::tele_bombed(
-                            state.player_pos_fr,
-                            state.score,
-                            num_aliens_i,
-                            state.lives,
-                        );
+                        continue;
+                    }
+                    for (pix, p) in state.players.iter().enumerate() {
+                        if p.state != PlayerState::Playing || bomb.dead {
+                            continue;
+                        }
+                        let playspr = ship::hitbox(p.ship, hitboxes);
+                        if p.shield_time > 0.0
+                            && bomb.ypos >= 1.33 - 0.06 - shieldspr.f_h * 0.5
+                            && bomb.ypos < 1.33 - 0.06 + shieldspr.f_h * 0.5
+                            && bomb.xpos >= p.pos_fr - shieldspr.f_w * 0.5
+                            && bomb.xpos <= p.pos_fr + shieldspr.f_w * 0.5
+                        {
+                            //  the bubble soaks it up
+                            bomb.dead = true;
+                            hasdeadbomb = true;
+                            state.explosions.push(explosion::new_small(
+                                &mut state.rng,
+                                bomb.xpos,
+                                bomb.ypos,
+                            ));
+                        } else if bomb.ypos >= 1.33 - 0.06 - playspr.f_h * 0.5
+                            && bomb.ypos < 1.33 - 0.06 + playspr.f_h * 0.5
+                            && bomb.xpos >= p.pos_fr - playspr.f_w * 0.45
+                            && bomb.xpos <= p.pos_fr + playspr.f_w * 0.45
+                        {
+                            bomb.dead = true;
+                            hasdeadbomb = true;
+                            bombed.push(pix);
+                        }
+                    }
+                }
+                for pix in bombed {
+                    if state.players[pix].state != PlayerState::Playing {
+                        continue;
+                    }
+                    player_hit(state, pix);
+                    let p = &state.players[pix];
+                    if !state.quiet {
+                        telemetry::tele_bombed(p.pos_fr, state.score, num_aliens_i, p.lives);
                     }
                 }
+
+                //  evolve power-ups
+                for p in state.players.iter_mut() {
+                    if p.rapid_fire_time > 0.0 {
+                        p.rapid_fire_time -= delta_time;
+                    }
+                    if p.spread_shot_time > 0.0 {
+                        p.spread_shot_time -= delta_time;
+                    }
+                    if p.shield_time > 0.0 {
+                        p.shield_time -= delta_time;
+                    }
+                }
+                let mut caught: Vec<(usize, &'static powerup::PowerupKind)> = Vec::new();
+                for pu in state.powerups.iter_mut() {
+                    powerup::evolve(delta_time, pu);
+                    let pspr: &sprite::Hitbox = &hitboxes[pu.kind.sprite.index];
+                    for (pix, p) in state.players.iter().enumerate() {
+                        let playspr = ship::hitbox(p.ship, hitboxes);
+                        if !pu.dead
+                            && p.state == PlayerState::Playing
+                            && pu.ypos + pspr.f_h * 0.5 >= 1.33 - 0.06 - playspr.f_h * 0.5
+                            && pu.ypos - pspr.f_h * 0.5 < 1.33 - 0.06 + playspr.f_h * 0.5
+                            && pu.xpos + pspr.f_w * 0.5 >= p.pos_fr - playspr.f_w * 0.5
+                            && pu.xpos - pspr.f_w * 0.5 <= p.pos_fr + playspr.f_w * 0.5
+                        {
+                            pu.dead = true;
+                            caught.push((pix, pu.kind));
+                        }
+                    }
+                }
+                for (pix, kind) in caught {
+                    apply_powerup(state, pix, kind);
+                }
+                state.powerups.retain(|p| !p.dead);
             } //  endif evolving
         }
 
-        //  TODO: barriers
-
         if hasdeadbullet {
             state.bullets.retain(|bullet| !bullet.dead);
         }
@@ -459,18 +1523,177 @@ pub fn update_state(delta_time: f32, state: &mut State, assets: &assets::Assets)
             state.explosions.retain(|x| !x.dead);
         }
 
-        if state.player_state == PlayerState::GameOver && mq::is_key_pressed(mq::KeyCode::Space) {
-            state.current_level = 0;
-            state.score = 0;
-            state.lives = 2;
-            state.reset_countdown = 1.2;
-            state.player_state = PlayerState::Playing;
-            state.aliens = Vec::new();
-            state.bombs = Vec::new();
-            state.bullets = Vec::new();
-            state.explosions = Vec::new();
-        } else {
+        if !is_game_over(state) {
             state.speed_ratio = 4.0 / (3.0 + num_aliens);
         }
     }
+
+    for (p, input) in state.players.iter_mut().zip(inputs.iter()) {
+        p.last_input = *input;
+    }
+}
+
+//  A finished game is over for good; the caller starts a new State (with a
+//  new seed) once anyone presses fire again.
+pub fn wants_restart(state: &State, inputs: &[input::Input]) -> bool {
+    let fire_pressed = state
+        .players
+        .iter()
+        .zip(inputs.iter())
+        .any(|(p, input)| input.fire && !p.last_input.fire);
+    return is_game_over(state) && fire_pressed;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //  A made-up player: wanders from side to side, fires most of the time
+    //  and now and then pauses, all from its own seeded rng so a run repeats
+    fn scripted_input(rng: &mut rng::Rng, held: &mut input::Input, tick: usize) -> input::Input {
+        if tick % 30 == 0 {
+            let way = rng::gen_range_usize(rng, 0, 3);
+            held.left = way == 0;
+            held.right = way == 1;
+        }
+        held.fire = rng::gen_range_usize(rng, 0, 4) != 0;
+        held.pause = tick % 5000 == 4000 || tick % 5000 == 4100;
+        return *held;
+    }
+
+    fn play(seed: u64, players: usize, ticks: usize) -> State {
+        let hitboxes = assets::load_hitboxes();
+        let waves = Rc::new(wave::load_waves_from_dir("html/data/waves"));
+        let ships = vec![ship::DEFAULT_SHIP; players];
+        let mut state = new_game_state(seed, &waves, &ships);
+        let mut rngs: Vec<rng::Rng> = (0..players)
+            .map(|ix| rng::new(seed * 10 + ix as u64))
+            .collect();
+        let mut held = vec![input::Input::default(); players];
+        for tick in 0..ticks {
+            let inputs: Vec<input::Input> = (0..players)
+                .map(|ix| scripted_input(&mut rngs[ix], &mut held[ix], tick))
+                .collect();
+            update_state(params::TICK_TIME, &mut state, &inputs, &hitboxes);
+            if is_game_over(&state) {
+                break;
+            }
+        }
+        return state;
+    }
+
+    //  Everything that can differ between two runs, down to the last bit
+    fn fingerprint(state: &State) -> Vec<u32> {
+        let mut ret = vec![
+            state.score as u32,
+            state.current_level as u32,
+            state.shots_fired as u32,
+            rng::next_u32(&mut state.rng.clone()),
+        ];
+        for p in state.players.iter() {
+            ret.extend([p.pos_fr.to_bits(), p.lives as u32, p.score as u32]);
+        }
+        for a in state.aliens.iter() {
+            ret.extend([a.xpos.to_bits(), a.ypos.to_bits(), a.hit_points as u32]);
+        }
+        for b in state.bullets.iter() {
+            ret.extend([b.xpos.to_bits(), b.ypos.to_bits()]);
+        }
+        for b in state.bombs.iter() {
+            ret.extend([b.xpos.to_bits(), b.ypos.to_bits()]);
+        }
+        for m in state.meteors.iter() {
+            ret.extend([m.xpos.to_bits(), m.ypos.to_bits()]);
+        }
+        for bk in state.bunkers.iter() {
+            ret.push(bk.cells.iter().map(|&c| c as u32).sum());
+        }
+        return ret;
+    }
+
+    #[test]
+    fn many_seeded_games_run_without_panicking() {
+        for seed in 0..40 {
+            let state = play(seed, 1, 60 * params::TICK_RATE as usize);
+            assert!(state.score >= 0, "seed {}", seed);
+        }
+        for seed in 0..10 {
+            let state = play(seed, 2, 60 * params::TICK_RATE as usize);
+            assert!(state.score >= 0, "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_game() {
+        for seed in [1, 7, 1234] {
+            for players in [1, 2] {
+                let ticks = 90 * params::TICK_RATE as usize;
+                let a = play(seed, players, ticks);
+                let b = play(seed, players, ticks);
+                assert!(fingerprint(&a) == fingerprint(&b), "seed {}", seed);
+            }
+        }
+    }
+
+    #[test]
+    fn an_alien_only_dies_once() {
+        //  a spread shot's three bullets all start out in the same place
+        let hitboxes = assets::load_hitboxes();
+        let mut state = new_game_state(1, &Rc::new(Vec::new()), &[ship::DEFAULT_SHIP]);
+        while state.aliens.is_empty() {
+            update_state(params::TICK_TIME, &mut state, &[], &hitboxes);
+        }
+        while state.reset_countdown > 0.0 {
+            update_state(params::TICK_TIME, &mut state, &[], &hitboxes);
+        }
+        let score = state.score;
+        let alien = state.aliens.pop().unwrap();
+        state.aliens = vec![alien.clone()];
+        state.bullets = (0..3)
+            .map(|_| {
+                let mut b = new_bullet(0, alien.xpos, 1.0, 0.0);
+                b.ypos = alien.ypos;
+                b
+            })
+            .collect();
+        state.outbox = Some(Vec::new());
+        update_state(params::TICK_TIME, &mut state, &[], &hitboxes);
+        assert!(state.aliens.is_empty());
+        assert_eq!(state.score, score + alien.points);
+        assert_eq!(state.players[0].score, score + alien.points);
+        assert_eq!(state.bullets.len(), 2);
+        assert_eq!(state.outbox.map(|o| o.len()), Some(1));
+    }
+
+    #[test]
+    fn each_player_goes_out_for_their_own_reason() {
+        let hitboxes = assets::load_hitboxes();
+        let ships = [ship::DEFAULT_SHIP, ship::DEFAULT_SHIP];
+        let mut state = new_game_state(1, &Rc::new(Vec::new()), &ships);
+        while state.aliens.is_empty() || state.reset_countdown > 0.0 {
+            update_state(params::TICK_TIME, &mut state, &[], &hitboxes);
+        }
+        //  as if overrun, then as if bombed on the last ship
+        let last_ship = |p: &mut Player| {
+            p.state = PlayerState::HitExploding;
+            p.hit_timer = params::TICK_TIME * 0.5;
+            p.lives = 0;
+        };
+        last_ship(&mut state.players[0]);
+        state.players[0].game_over_reason = GameOverReason::Overrun;
+        update_state(params::TICK_TIME, &mut state, &[], &hitboxes);
+        assert!(state.players[0].state == PlayerState::GameOver);
+        assert!(game_over_reason(&state) == GameOverReason::Overrun);
+        last_ship(&mut state.players[1]);
+        update_state(params::TICK_TIME, &mut state, &[], &hitboxes);
+        assert!(is_game_over(&state));
+        assert!(game_over_reason(&state) == GameOverReason::OutOfLives);
+        assert!(state.players[0].game_over_reason == GameOverReason::Overrun);
+    }
+
+    #[test]
+    fn different_seeds_different_games() {
+        let ticks = 30 * params::TICK_RATE as usize;
+        assert!(fingerprint(&play(1, 1, ticks)) != fingerprint(&play(2, 1, ticks)));
+    }
 }
@@ -1,18 +1,25 @@
 //  telemetry.rs
+use crate::js::JsObject;
 use crate::params;
-use sapp_jsutils::JsObject;
 use std::borrow::Borrow;
 
 /*  See also:
     https://github.com/not-fl3/miniquad-js-interop-demo/blob/master/js/demo.js
 */
 
+#[cfg(target_arch = "wasm32")]
 extern "C" {
     fn queue_telemetry(argtype: JsObject, arg: JsObject);
 }
 
-pub fn tele_startup() {
+#[cfg(not(target_arch = "wasm32"))]
+unsafe fn queue_telemetry(_argtype: JsObject, _arg: JsObject) {}
+
+//  Sent once a session, when the first ship has been picked
+pub fn tele_startup(hull: &str, colour: &str) {
     let obj = JsObject::object();
+    obj.set_field_string("hull", hull);
+    obj.set_field_string("colour", colour);
     //  Include game parameters
     obj.set_field_string("VERSION", params::VERSION);
     obj.set_field_f32("HORIZ_SPEED", params::HORIZ_SPEED);
@@ -70,6 +77,20 @@ pub fn tele_hit(xpos: f32, score: i32, kind: usize, points: i32, remaining: usiz
     }
 }
 
+//  an armored alien took a hit but survived
+pub fn tele_damage(xpos: f32, score: i32, kind: usize, hit_points: i32, remaining: usize) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_string("kind", format!("{}", kind).borrow());
+    obj.set_field_f32("hit_points", hit_points as f32);
+    obj.set_field_f32("remaining", remaining as f32);
+    let kind = JsObject::string("damage");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
 pub fn tele_miss(xpos: f32, score: i32, remaining: usize) {
     let obj = JsObject::object();
     obj.set_field_f32("xpos", xpos);
@@ -112,14 +133,129 @@ pub fn tele_bombed(xpos: f32, score: i32, remaining: usize, lives: i32) {
     }
 }
 
-pub fn tele_game_over(xpos: f32, score: i32, remaining: usize, level: i32) {
+//  a diving alien crashed into the player
+pub fn tele_rammed(xpos: f32, score: i32, remaining: usize, lives: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_f32("remaining", remaining as f32);
+    obj.set_field_f32("lives", lives as f32);
+    let kind = JsObject::string("rammed");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_game_over(xpos: f32, score: i32, remaining: usize, level: i32, reason: &str) {
     let obj = JsObject::object();
     obj.set_field_f32("xpos", xpos);
     obj.set_field_f32("score", score as f32);
     obj.set_field_f32("remaining", remaining as f32);
     obj.set_field_f32("level", level as f32);
+    obj.set_field_string("reason", reason);
     let kind = JsObject::string("game_over");
     unsafe {
         queue_telemetry(kind, obj);
     }
 }
+
+pub fn tele_ufo_hit(xpos: f32, score: i32, points: i32, shots: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_f32("points", points as f32);
+    obj.set_field_f32("shots", shots as f32);
+    let kind = JsObject::string("ufo_hit");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_ufo_escaped(score: i32, shots: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_f32("shots", shots as f32);
+    let kind = JsObject::string("ufo_escaped");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_bomb_shot(xpos: f32, score: i32, kind: usize, points: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_string("kind", format!("{}", kind).borrow());
+    obj.set_field_f32("points", points as f32);
+    let kind = JsObject::string("bomb_shot");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_powerup(xpos: f32, score: i32, effect: &str) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_string("effect", effect);
+    let kind = JsObject::string("powerup");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_boss_appeared(level: i32, score: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("level", level as f32);
+    obj.set_field_f32("score", score as f32);
+    let kind = JsObject::string("boss_appeared");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_boss_defeated(xpos: f32, score: i32, points: i32, level: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_f32("points", points as f32);
+    obj.set_field_f32("level", level as f32);
+    let kind = JsObject::string("boss_defeated");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_meteor_shower(level: i32, score: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("level", level as f32);
+    obj.set_field_f32("score", score as f32);
+    let kind = JsObject::string("meteor_shower");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_meteor_shot(xpos: f32, score: i32, size: &str, points: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_string("size", size);
+    obj.set_field_f32("points", points as f32);
+    let kind = JsObject::string("meteor_shot");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
+
+pub fn tele_meteor_hit(xpos: f32, score: i32, size: &str, lives: i32) {
+    let obj = JsObject::object();
+    obj.set_field_f32("xpos", xpos);
+    obj.set_field_f32("score", score as f32);
+    obj.set_field_string("size", size);
+    obj.set_field_f32("lives", lives as f32);
+    let kind = JsObject::string("meteor_hit");
+    unsafe {
+        queue_telemetry(kind, obj);
+    }
+}
@@ -0,0 +1,59 @@
+//  trig.rs
+
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
+
+//  Sine and cosine for the simulation. f32::sin() and friends come from
+//  whatever libm the target has, and the browser's and the desktop's don't
+//  always agree to the last bit; a replay recorded in one is checked in the
+//  other, and netplay compares checksums, so any wobble shows up as a
+//  desync. These use nothing but +, -, * and floor(), which IEEE pins down
+//  exactly everywhere. Good to about 1e-7, plenty for moving things about;
+//  rendering can keep using the std ones.
+
+//  Taylor series for sin() on [-PI/2, PI/2], highest power first
+const SIN_COEFFS: [f32; 6] = [
+    -1.0 / 39916800.0,
+    1.0 / 362880.0,
+    -1.0 / 5040.0,
+    1.0 / 120.0,
+    -1.0 / 6.0,
+    1.0,
+];
+
+pub fn sin(x: f32) -> f32 {
+    //  into [-PI, PI], then folded into [-PI/2, PI/2]
+    let turns = x / TAU;
+    let mut r = (turns - (turns + 0.5).floor()) * TAU;
+    if r > FRAC_PI_2 {
+        r = PI - r;
+    } else if r < -FRAC_PI_2 {
+        r = -PI - r;
+    }
+    let r2 = r * r;
+    let mut ret = 0.0;
+    for c in SIN_COEFFS {
+        ret = ret * r2 + c;
+    }
+    return ret * r;
+}
+
+pub fn cos(x: f32) -> f32 {
+    return sin(x + FRAC_PI_2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn close_to_std() {
+        let mut x: f32 = -50.0;
+        while x < 50.0 {
+            //  far from 0, x itself is only good to a few parts in 1e7
+            let slack = 1e-6 + x.abs() * 2e-7;
+            assert!((sin(x) - x.sin()).abs() < slack, "sin {}", x);
+            assert!((cos(x) - x.cos()).abs() < slack, "cos {}", x);
+            x += 0.01;
+        }
+    }
+}
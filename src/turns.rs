@@ -0,0 +1,40 @@
+//  turns.rs
+
+use crate::state;
+
+//  Two players taking turns, as on the arcade cabinet. Each player has a
+//  whole game of their own: formation, bunkers, score and lives. Only the
+//  one whose turn it is gets simulated; the other waits, frozen where their
+//  last ship went down, and picks up from there on their next turn.
+
+pub struct Turns {
+    pub waiting: state::State,
+    pub current: usize, //  whose turn it is; 0 is player 1
+}
+
+pub fn new(waiting: state::State) -> Turns {
+    return Turns {
+        waiting: waiting,
+        current: 0,
+    };
+}
+
+//  Call once the active player's turn_over is set. Returns true if the
+//  other player takes over; someone out of lives gets no more turns.
+pub fn end_turn(turns: &mut Turns, active: &mut state::State) -> bool {
+    active.turn_over = false;
+    if state::is_game_over(&turns.waiting) {
+        return false;
+    }
+    std::mem::swap(active, &mut turns.waiting);
+    turns.current = 1 - turns.current;
+    return true;
+}
+
+//  Player 1 or 2's game (ix 0 or 1), whichever of the two is playing
+pub fn player<'a>(turns: &'a Turns, active: &'a state::State, ix: usize) -> &'a state::State {
+    if ix == turns.current {
+        return active;
+    }
+    return &turns.waiting;
+}
@@ -0,0 +1,84 @@
+//  ufo.rs
+
+use crate::assets;
+use crate::rng;
+use crate::sprite;
+
+pub const UFO_Y: f32 = 0.11;
+const UFO_SPEED: f32 = 0.16;
+const UFO_MIN_TIME: f32 = 18.0;
+const UFO_EXTRA_TIME: f32 = 10.0;
+const SHOW_POINTS_TIME: f32 = 1.0;
+//  The UFO doesn't bother showing up for the last few aliens
+pub const UFO_MIN_ALIENS: usize = 8;
+
+//  The arcade's mystery score isn't random: it's looked up by how many shots
+//  the player has fired, so the 23rd shot and every 15th after it is worth 300.
+static SHOT_POINTS: [i32; 15] = [
+    100, 50, 50, 100, 150, 100, 100, 50, 300, 100, 100, 100, 50, 150, 100,
+];
+
+#[derive(Clone)]
+pub struct Ufo {
+    pub sprite: &'static sprite::Sprite,
+    pub xpos: f32,
+    pub last_xpos: f32, //  used for render interpolation
+    pub velocity: f32,
+    pub phase: f32,
+    pub points: i32,       //  awarded when hit, then shown in its place
+    pub points_timer: f32, //  > 0 while showing the points
+    pub dead: bool,
+}
+
+pub fn time_to_next(rng: &mut rng::Rng) -> f32 {
+    return rng::gen_range_f32(rng, UFO_MIN_TIME, UFO_MIN_TIME + UFO_EXTRA_TIME);
+}
+
+//  Like the arcade, which side it comes from depends on the shot count
+pub fn new(rng: &mut rng::Rng, shots_fired: i32) -> Ufo {
+    let ix = rng::gen_range_usize(rng, 0, assets::UFO_SPRITES.len());
+    let from_left = shots_fired % 2 == 0;
+    let xpos = if from_left { -0.05 } else { 1.05 };
+    return Ufo {
+        sprite: &assets::UFO_SPRITES[ix],
+        xpos: xpos,
+        last_xpos: xpos,
+        velocity: if from_left { UFO_SPEED } else { -UFO_SPEED },
+        phase: 0.0,
+        points: 0,
+        points_timer: 0.0,
+        dead: false,
+    };
+}
+
+pub fn points_for_shot(shots_fired: i32) -> i32 {
+    return SHOT_POINTS[(shots_fired.max(0) as usize) % SHOT_POINTS.len()];
+}
+
+pub fn is_shown_as_points(u: &Ufo) -> bool {
+    return u.points_timer > 0.0;
+}
+
+pub fn hit(u: &mut Ufo, points: i32) {
+    u.points = points;
+    u.points_timer = SHOW_POINTS_TIME;
+    u.velocity = 0.0;
+}
+
+//  Returns true if the UFO made it off the other side of the screen
+pub fn evolve(dt: f32, u: &mut Ufo) -> bool {
+    if is_shown_as_points(u) {
+        u.points_timer -= dt;
+        if u.points_timer <= 0.0 {
+            u.dead = true;
+        }
+        return false;
+    }
+    u.xpos += u.velocity * dt;
+    u.phase += dt * 3.0;
+    if (u.velocity > 0.0 && u.xpos > 1.05) || (u.velocity < 0.0 && u.xpos < -0.05) {
+        u.dead = true;
+        return true;
+    }
+    return false;
+}
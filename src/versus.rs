@@ -0,0 +1,287 @@
+//  versus.rs
+
+#[cfg(target_arch = "wasm32")]
+use crate::js::JsObject;
+use crate::state;
+use macroquad::prelude as mq;
+use quad_net::quad_socket::client::QuadSocket;
+
+//  Head to head over the network. Both sides play their own game from the
+//  same seed, and every alien one side destroys sends an extra alien or a
+//  bomb over to the other's field. The relay (src/bin/relay.rs) pairs
+//  players up and passes messages between them; it doesn't run any game.
+//
+//  Desktop builds talk to the relay over TCP; the web build, which can't,
+//  uses its WebSocket port.
+
+pub const RELAY_TCP_ADDR: &str = "127.0.0.1:8090";
+pub const RELAY_WS_ADDR: &str = "127.0.0.1:8091";
+pub const RELAY_WS_URL: &str = "ws://127.0.0.1:8091";
+
+//  How often each side tells the other its score
+const STATUS_INTERVAL: f32 = 0.25;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Attack {
+    Alien, //  joins the top of the formation
+    Bomb,  //  drops from above
+}
+
+//  The top rows are worth the most and send the worse of the two
+pub fn attack_for_kill(points: i32) -> Attack {
+    if points >= 30 {
+        return Attack::Alien;
+    }
+    return Attack::Bomb;
+}
+
+//  What a player wants the relay to find them; it only pairs like with like
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Game {
+    Versus,
+    CoOp, //  see netplay.rs
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Message {
+    Hello(Game),      //  to the relay: find me an opponent
+    Start(u64, u8),   //  from the relay, to both sides: play this seed, as player n
+    Attack(Attack),   //  passed along to the opponent
+    Status(i32, i32), //  score and lives, passed along
+    Lost,             //  this side is out of the game
+    OpponentLeft,     //  from the relay: the other side disconnected
+}
+
+//  One tag byte, then any payload, little endian. Tags from 16 up are
+//  netplay.rs's, which the relay passes along without looking.
+const TAG_HELLO: u8 = 1;
+const TAG_START: u8 = 2;
+const TAG_ATTACK_ALIEN: u8 = 3;
+const TAG_ATTACK_BOMB: u8 = 4;
+const TAG_STATUS: u8 = 5;
+const TAG_LOST: u8 = 6;
+const TAG_OPPONENT_LEFT: u8 = 7;
+
+pub fn encode(msg: &Message) -> Vec<u8> {
+    let mut ret: Vec<u8> = Vec::new();
+    match *msg {
+        Message::Hello(game) => {
+            ret.push(TAG_HELLO);
+            ret.push(game as u8);
+        }
+        Message::Start(seed, seat) => {
+            ret.push(TAG_START);
+            ret.extend_from_slice(&seed.to_le_bytes());
+            ret.push(seat);
+        }
+        Message::Attack(Attack::Alien) => ret.push(TAG_ATTACK_ALIEN),
+        Message::Attack(Attack::Bomb) => ret.push(TAG_ATTACK_BOMB),
+        Message::Status(score, lives) => {
+            ret.push(TAG_STATUS);
+            ret.extend_from_slice(&score.to_le_bytes());
+            ret.extend_from_slice(&lives.to_le_bytes());
+        }
+        Message::Lost => ret.push(TAG_LOST),
+        Message::OpponentLeft => ret.push(TAG_OPPONENT_LEFT),
+    }
+    return ret;
+}
+
+fn payload<const N: usize>(data: &[u8]) -> Result<[u8; N], String> {
+    return data
+        .get(1..1 + N)
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("message {} truncated", data[0]));
+}
+
+pub fn decode(data: &[u8]) -> Result<Message, String> {
+    if data.is_empty() {
+        return Err("empty message".to_string());
+    }
+    return match data[0] {
+        TAG_HELLO => match payload::<1>(data)?[0] {
+            0 => Ok(Message::Hello(Game::Versus)),
+            _ => Ok(Message::Hello(Game::CoOp)),
+        },
+        TAG_START => {
+            let p = payload::<9>(data)?;
+            let seed = u64::from_le_bytes([p[0], p[1], p[2], p[3], p[4], p[5], p[6], p[7]]);
+            Ok(Message::Start(seed, p[8]))
+        }
+        TAG_ATTACK_ALIEN => Ok(Message::Attack(Attack::Alien)),
+        TAG_ATTACK_BOMB => Ok(Message::Attack(Attack::Bomb)),
+        TAG_STATUS => {
+            let p = payload::<8>(data)?;
+            let score = i32::from_le_bytes([p[0], p[1], p[2], p[3]]);
+            let lives = i32::from_le_bytes([p[4], p[5], p[6], p[7]]);
+            Ok(Message::Status(score, lives))
+        }
+        TAG_LOST => Ok(Message::Lost),
+        TAG_OPPONENT_LEFT => Ok(Message::OpponentLeft),
+        tag => Err(format!("unknown message {}", tag)),
+    };
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Playing,
+    Won,
+    Lost,
+    OpponentLeft,
+}
+
+pub struct Versus {
+    pub socket: QuadSocket,
+    pub opponent_score: i32,
+    pub opponent_lives: i32,
+    pub opponent_out: bool,
+    pub outcome: Outcome, //  whoever goes out first loses
+    pub status_timer: f32,
+    pub lost_sent: bool,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+const RELAY_ADDR: &str = RELAY_TCP_ADDR;
+#[cfg(target_arch = "wasm32")]
+const RELAY_ADDR: &str = RELAY_WS_URL;
+
+pub fn connect() -> Result<Versus, String> {
+    let socket = QuadSocket::connect(RELAY_ADDR)
+        .map_err(|e| format!("can't reach the relay at {}: {:?}", RELAY_ADDR, e))?;
+    return Ok(Versus {
+        socket: socket,
+        opponent_score: 0,
+        opponent_lives: 0,
+        opponent_out: false,
+        outcome: Outcome::Playing,
+        status_timer: 0.0,
+        lost_sent: false,
+    });
+}
+
+//  A TCP connect has finished by the time it returns; a browser's socket
+//  opens in the background, and nothing can be sent until it has
+#[cfg(not(target_arch = "wasm32"))]
+pub fn is_connected(_v: &Versus) -> bool {
+    return true;
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn is_connected(v: &Versus) -> bool {
+    return v.socket.is_wasm_websocket_connected();
+}
+
+#[cfg(target_arch = "wasm32")]
+extern "C" {
+    fn ws_try_recv() -> JsObject;
+}
+
+//  quad-net 0.1.1's browser try_recv copies a binary message into a buffer
+//  that goes out of scope, and hands back an empty one. Every message here
+//  is binary, so the web build takes them from quad-net's socket itself.
+//  The page only ever has the one socket, so there's no saying which.
+#[cfg(target_arch = "wasm32")]
+pub fn browser_try_recv() -> Option<Vec<u8>> {
+    let obj = unsafe { ws_try_recv() };
+    if obj.is_nil() {
+        return None;
+    }
+    let mut ret: Vec<u8> = Vec::new();
+    if obj.field_u32("text") == 1 {
+        let mut text = String::new();
+        obj.field("data").to_string(&mut text);
+        ret = text.into_bytes();
+    } else {
+        obj.field("data").to_byte_buffer(&mut ret);
+    }
+    return Some(ret);
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn try_recv(v: &mut Versus) -> Option<Vec<u8>> {
+    return v.socket.try_recv();
+}
+
+#[cfg(target_arch = "wasm32")]
+fn try_recv(_v: &mut Versus) -> Option<Vec<u8>> {
+    return browser_try_recv();
+}
+
+pub fn send(v: &mut Versus, msg: &Message) {
+    v.socket.send(&encode(msg));
+}
+
+pub fn receive(v: &mut Versus) -> Option<Message> {
+    while let Some(data) = try_recv(v) {
+        match decode(&data) {
+            Ok(msg) => return Some(msg),
+            Err(e) => mq::warn!("versus: {}", e),
+        }
+    }
+    return None;
+}
+
+//  Ask the relay for a (new) opponent; poll receive() for the Start
+pub fn find_match(v: &mut Versus) {
+    v.opponent_score = 0;
+    v.opponent_lives = 0;
+    v.opponent_out = false;
+    v.outcome = Outcome::Playing;
+    v.status_timer = 0.0;
+    v.lost_sent = false;
+    send(v, &Message::Hello(Game::Versus));
+}
+
+//  Once a frame, after the ticks: take in what the opponent sent, and send
+//  them what this side earned
+pub fn exchange(v: &mut Versus, state: &mut state::State, delta_time: f32) {
+    while let Some(msg) = receive(v) {
+        match msg {
+            Message::Attack(attack) => state::receive_attack(state, attack),
+            Message::Status(score, lives) => {
+                v.opponent_score = score;
+                v.opponent_lives = lives;
+            }
+            Message::Lost => {
+                v.opponent_out = true;
+                if v.outcome == Outcome::Playing {
+                    v.outcome = Outcome::Won;
+                }
+            }
+            Message::OpponentLeft => {
+                v.opponent_out = true;
+                if v.outcome == Outcome::Playing {
+                    v.outcome = Outcome::OpponentLeft;
+                }
+            }
+            Message::Hello(_) | Message::Start(_, _) => {}
+        }
+    }
+
+    //  attacks only matter while the opponent is still there to take them
+    let attacks: Vec<Attack> = match &mut state.outbox {
+        Some(out) => std::mem::take(out),
+        None => Vec::new(),
+    };
+    if !v.opponent_out {
+        for attack in attacks {
+            send(v, &Message::Attack(attack));
+        }
+    }
+
+    v.status_timer -= delta_time;
+    if v.status_timer <= 0.0 {
+        v.status_timer = STATUS_INTERVAL;
+        let lives = state.players.iter().map(|p| p.lives).sum();
+        send(v, &Message::Status(state.score, lives));
+    }
+
+    if state::is_game_over(state) && !v.lost_sent {
+        v.lost_sent = true;
+        send(v, &Message::Status(state.score, 0));
+        send(v, &Message::Lost);
+        if v.outcome == Outcome::Playing {
+            v.outcome = Outcome::Lost;
+        }
+    }
+}
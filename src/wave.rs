@@ -0,0 +1,238 @@
+//  wave.rs
+
+use crate::assets;
+use crate::sprite;
+use macroquad::prelude as mq;
+use nanoserde::DeRon;
+
+//  Alien formations, as written in data/waves/waveN.ron for level N.
+//  Any level without a (valid) file gets the classic five-row layout.
+//
+//  (
+//      start_y: 0.2,   //  optional, else the levels table decides
+//      dive_interval: 4.0, //  optional; roughly this many seconds between dives
+//      rows: [
+//          (sprite: "enemy3", points: 30),
+//          (sprite: "enemy2", points: 20, columns: 8, hit_points: 2),
+//      ],
+//  )
+
+#[derive(DeRon, Clone)]
+pub struct WaveRow {
+    pub sprite: String,
+    pub points: i32,
+    #[nserde(default = "10")]
+    pub columns: i32,
+    #[nserde(default = "1")]
+    pub hit_points: i32,
+}
+
+#[derive(DeRon, Clone)]
+pub struct Wave {
+    pub start_y: Option<f32>,
+    pub dive_interval: Option<f32>,
+    pub rows: Vec<WaveRow>,
+}
+
+pub const MAX_COLUMNS: i32 = 12;
+//  Rows are 0.1 apart, so the deepest wave allowed still has its bottom row
+//  up at 1.0, clear of the bunkers and well clear of the ground
+pub const MAX_ROWS: usize = 6;
+pub const MIN_START_Y: f32 = 0.15; //  below the UFO
+pub const MAX_START_Y: f32 = 0.5;
+const MAX_WAVE_FILES: i32 = 99;
+
+pub fn sprite_by_name(name: &str) -> Option<&'static sprite::Sprite> {
+    return match name {
+        "enemy1" => Some(&assets::ENEMY1_SPRITE),
+        "enemy2" => Some(&assets::ENEMY2_SPRITE),
+        "enemy3" => Some(&assets::ENEMY3_SPRITE),
+        _ => None,
+    };
+}
+
+fn classic_row(sprite: &str, points: i32) -> WaveRow {
+    return WaveRow {
+        sprite: sprite.to_string(),
+        points: points,
+        columns: 10,
+        hit_points: 1,
+    };
+}
+
+pub fn classic() -> Wave {
+    return Wave {
+        start_y: None,
+        dive_interval: None,
+        rows: vec![
+            classic_row("enemy3", 30),
+            classic_row("enemy2", 20),
+            classic_row("enemy2", 20),
+            classic_row("enemy1", 10),
+            classic_row("enemy1", 10),
+        ],
+    };
+}
+
+pub fn parse(text: &str) -> Result<Wave, String> {
+    let wave = Wave::deserialize_ron(text).map_err(|e| format!("{}", e))?;
+    if wave.rows.is_empty() {
+        return Err("wave has no rows".to_string());
+    }
+    if wave.rows.len() > MAX_ROWS {
+        return Err(format!("a wave has at most {} rows", MAX_ROWS));
+    }
+    //  written so that NaN fails too
+    if wave
+        .start_y
+        .is_some_and(|y| !(MIN_START_Y..=MAX_START_Y).contains(&y))
+    {
+        return Err(format!(
+            "start_y must be {} to {}",
+            MIN_START_Y, MAX_START_Y
+        ));
+    }
+    if wave.dive_interval.is_some_and(|d| d.is_nan() || d <= 0.0) {
+        return Err("dive_interval must be more than 0".to_string());
+    }
+    for row in wave.rows.iter() {
+        if sprite_by_name(&row.sprite).is_none() {
+            return Err(format!("unknown sprite \"{}\"", row.sprite));
+        }
+        if row.columns < 1 || row.columns > MAX_COLUMNS {
+            return Err(format!("columns must be 1 to {}", MAX_COLUMNS));
+        }
+        if row.hit_points < 1 {
+            return Err("hit_points must be at least 1".to_string());
+        }
+    }
+    return Ok(wave);
+}
+
+//  Levels count from 1, as do wave files
+pub fn for_level(waves: &[Wave], level: i32) -> Wave {
+    if level >= 1 && ((level - 1) as usize) < waves.len() {
+        return waves[(level - 1) as usize].clone();
+    }
+    return classic();
+}
+
+fn wave_file(n: i32) -> String {
+    return format!("data/waves/wave{}.ron", n);
+}
+
+//  Reads wave1.ron, wave2.ron, ... up to the first one that's missing.
+//  A file that doesn't parse still holds its level's place, as the classic wave.
+pub async fn load_waves() -> Vec<Wave> {
+    let mut ret: Vec<Wave> = Vec::new();
+    for n in 1..=MAX_WAVE_FILES {
+        let text = match mq::load_string(&wave_file(n)).await {
+            Ok(text) => text,
+            Err(_) => break,
+        };
+        match parse(&text) {
+            Ok(w) => ret.push(w),
+            Err(e) => {
+                mq::warn!("{}: {}", wave_file(n), e);
+                ret.push(classic());
+            }
+        }
+    }
+    mq::info!("loaded {} wave files", ret.len());
+    return ret;
+}
+
+//  The same, for headless tools; dir is what the game calls "data/waves"
+pub fn load_waves_from_dir(dir: &str) -> Vec<Wave> {
+    let mut ret: Vec<Wave> = Vec::new();
+    for n in 1..=MAX_WAVE_FILES {
+        let path = format!("{}/wave{}.ron", dir, n);
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => break,
+        };
+        match parse(&text) {
+            Ok(w) => ret.push(w),
+            Err(e) => {
+                eprintln!("{}: {}", path, e);
+                ret.push(classic());
+            }
+        }
+    }
+    return ret;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_with_defaults() {
+        let text = r#"(
+            start_y: 0.3,
+            rows: [
+                (sprite: "enemy3", points: 30),
+                (sprite: "enemy1", points: 10, columns: 6, hit_points: 3),
+            ],
+        )"#;
+        let wave = parse(text).unwrap();
+        assert_eq!(wave.start_y, Some(0.3));
+        assert_eq!(wave.dive_interval, None);
+        assert_eq!(wave.rows.len(), 2);
+        assert_eq!(wave.rows[0].columns, 10);
+        assert_eq!(wave.rows[0].hit_points, 1);
+        assert_eq!(wave.rows[1].columns, 6);
+        assert_eq!(wave.rows[1].hit_points, 3);
+    }
+
+    #[test]
+    fn rejects_bad_waves() {
+        let bad = [
+            "not ron at all",
+            "(rows: [])",
+            r#"(rows: [(sprite: "enemy9", points: 10)])"#,
+            r#"(rows: [(sprite: "enemy1", points: 10, columns: 0)])"#,
+            r#"(rows: [(sprite: "enemy1", points: 10, columns: 13)])"#,
+            r#"(rows: [(sprite: "enemy1", points: 10, hit_points: 0)])"#,
+            r#"(dive_interval: 0.0, rows: [(sprite: "enemy1", points: 10)])"#,
+            r#"(start_y: -0.2, rows: [(sprite: "enemy1", points: 10)])"#,
+            r#"(start_y: 1.2, rows: [(sprite: "enemy1", points: 10)])"#,
+            r#"(start_y: NaN, rows: [(sprite: "enemy1", points: 10)])"#,
+            r#"(rows: [
+                (sprite: "enemy3", points: 30), (sprite: "enemy3", points: 30),
+                (sprite: "enemy2", points: 20), (sprite: "enemy2", points: 20),
+                (sprite: "enemy1", points: 10), (sprite: "enemy1", points: 10),
+                (sprite: "enemy1", points: 10),
+            ])"#,
+        ];
+        for text in bad {
+            assert!(parse(text).is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn levels_without_a_wave_get_the_classic_one() {
+        let waves = vec![parse(r#"(rows: [(sprite: "enemy2", points: 20)])"#).unwrap()];
+        assert_eq!(for_level(&waves, 1).rows.len(), 1);
+        for level in [0, 2, 50] {
+            let wave = for_level(&waves, level);
+            assert_eq!(wave.rows.len(), classic().rows.len(), "level {}", level);
+            assert_eq!(wave.rows[0].sprite, "enemy3");
+        }
+        assert!(load_waves_from_dir("no/such/dir").is_empty());
+    }
+
+    #[test]
+    fn shipped_waves_all_parse() {
+        for n in 1..=MAX_WAVE_FILES {
+            let path = format!("html/{}", wave_file(n));
+            match std::fs::read_to_string(&path) {
+                Ok(text) => assert!(parse(&text).is_ok(), "{}", path),
+                Err(_) => {
+                    assert!(n > 1, "no wave files in html/data/waves");
+                    break;
+                }
+            }
+        }
+    }
+}